@@ -10,7 +10,7 @@ manager.start();
 // Create a window for the animation
 const width = 600;
 const height = 400;
-const windowId = Number(manager.createWindow(width, height, "Bouncing Ball Animation"));
+const windowId = manager.createWindow(width, height, "Bouncing Ball Animation").id;
 console.log(`Animation window created with ID: ${windowId}`);
 
 // Ball properties
@@ -30,7 +30,8 @@ const maxFrames = 600; // Run for ~10 seconds at 60fps
 
 console.log("Starting animation...");
 
-const animationInterval = setInterval(() => {
+// Frames are paced by the event loop to the monitor refresh rate
+function frame(_timestamp: number, _delta: number) {
   frameCount++;
   
   // Clear to black
@@ -82,12 +83,15 @@ const animationInterval = setInterval(() => {
   
   // Stop after max frames
   if (frameCount >= maxFrames) {
-    clearInterval(animationInterval);
     console.log("Animation complete!");
     console.log(`Final ball position: (${Math.floor(ballX)}, ${Math.floor(ballY)})`);
     console.log("Window remains open.");
+  } else {
+    manager.requestAnimationFrame(windowId, frame);
   }
-}, 16); // ~60 FPS
+}
+
+manager.requestAnimationFrame(windowId, frame);
 
 // Helper function to draw a filled circle
 function drawCircle(cx: number, cy: number, radius: number, r: number, g: number, b: number) {
@@ -0,0 +1,39 @@
+import { WindowManager } from "../index";
+
+// Demo: Application id, WM_CLASS and a window icon
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+
+// Start the event loop
+manager.start();
+
+// Windows sharing a class name are grouped together in the taskbar
+const windows = [0, 1].map((i) =>
+  manager.createWindowWithOptions(320, 200, `Editor ${i + 1}`, {
+    x: 100 + i * 360,
+    y: 100,
+    appId: "gpui-editor",
+    className: "GpuiEditor",
+  })
+);
+
+// A 32x32 icon: a diagonal gradient with a transparent border
+const size = 32;
+const icon = new Uint8Array(size * size * 4);
+for (let y = 0; y < size; y++) {
+  for (let x = 0; x < size; x++) {
+    const i = (y * size + x) * 4;
+    const border = x < 2 || y < 2 || x >= size - 2 || y >= size - 2;
+    icon[i] = x * 8;
+    icon[i + 1] = 96;
+    icon[i + 2] = y * 8;
+    icon[i + 3] = border ? 0 : 255;
+  }
+}
+
+for (const window of windows) {
+  window.clear(30, 30, 36);
+  window.present();
+  manager.setIcon(window.id, icon, size, size);
+}
+console.log("Created two grouped windows with an icon");
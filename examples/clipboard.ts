@@ -0,0 +1,31 @@
+import { WindowManager } from "../index";
+
+// Demo: Copy and paste text and images through the clipboard and the primary selection
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+const clipboard = manager.clipboard;
+
+// Text: whatever is on the clipboard now, then our own
+console.log(`Clipboard text: ${JSON.stringify(clipboard.readText())}`);
+clipboard.writeText("Copied from gpui-napi");
+console.log(`After writeText: ${JSON.stringify(clipboard.readText())}`);
+
+// The primary selection is what middle-click pastes
+clipboard.writeText("Selected in gpui-napi", "primary");
+console.log(`Primary selection: ${JSON.stringify(clipboard.readText("primary"))}`);
+
+// Images are straight RGBA bytes, offered to other programs as PNG
+const size = 16;
+const rgba = new Uint8Array(size * size * 4);
+for (let i = 0; i < size * size; i++) {
+  rgba.set([255, (i % size) * 16, 0, 255], i * 4);
+}
+clipboard.writeImage(rgba, size, size);
+
+const image = clipboard.readImage();
+if (image) {
+  console.log(`Clipboard image: ${image.width}x${image.height}, ${image.data.length} bytes`);
+}
+
+// Our contents stay available to other programs until this process exits
+setTimeout(() => console.log("Done"), 10000);
@@ -10,7 +10,7 @@ manager.start();
 // Create a canvas window
 const width = 500;
 const height = 500;
-const windowId = Number(manager.createWindow(width, height, "Drawing Canvas Demo"));
+const windowId = manager.createWindow(width, height, "Drawing Canvas Demo").id;
 console.log(`Canvas window created with ID: ${windowId}`);
 
 // Clear to dark gray background
@@ -0,0 +1,29 @@
+import { WindowManager } from "../index";
+
+// Demo: A modal dialog that stays above its parent and closes with it
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+
+// Start the event loop
+manager.start();
+
+const main = manager.createWindow(640, 480, "Main Window");
+main.clear(40, 44, 52);
+main.present();
+
+// The dialog keeps focus while it is open and is closed together with the main window
+const dialog = manager.createWindow(320, 160, "Confirm", {
+  windowType: "dialog",
+  parent: main.id,
+  modal: true,
+  blockParentInput: true,
+});
+dialog.clear(60, 64, 72);
+dialog.present();
+console.log(`Dialog ${dialog.id} has parent ${dialog.parent}`);
+
+// Closing the main window also closes the dialog
+setTimeout(() => {
+  manager.closeWindow(main.id);
+  console.log("Closed the main window and its dialog");
+}, 5000);
@@ -0,0 +1,45 @@
+import { WindowManager } from "../index";
+
+// Demo: One window per monitor, like a multi-screen kiosk
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+
+// Start the event loop
+manager.start();
+
+// List the connected monitors (positions and sizes are physical pixels)
+const monitors = manager.getMonitors();
+for (const monitor of monitors) {
+  const area = monitor.workArea;
+  console.log(
+    `Monitor ${monitor.index}: ${monitor.name ?? "unknown"} ${monitor.width}x${monitor.height} ` +
+      `at (${monitor.x}, ${monitor.y}), scale ${monitor.scaleFactor}, ` +
+      `${monitor.refreshRate ?? "?"} Hz${monitor.primary ? ", primary" : ""}`
+  );
+  console.log(`  Work area: ${area.width}x${area.height} at (${area.x}, ${area.y})`);
+}
+
+// Open a window centered on each monitor, plus a small status window in a corner
+const colors: Array<[number, number, number]> = [
+  [200, 60, 60],
+  [60, 200, 60],
+  [60, 60, 200],
+];
+
+for (const monitor of monitors) {
+  const window = manager.createWindow(400, 300, `Monitor ${monitor.index}`);
+  const [r, g, b] = colors[monitor.index % colors.length];
+  window.clear(r, g, b);
+  window.present();
+  manager.centerOnMonitor(window.id, monitor.index);
+
+  const status = manager.createWindowWithOptions(200, 60, "Status", {
+    alwaysOnTop: true,
+    decorations: false,
+  });
+  status.clear(30, 30, 30);
+  status.present();
+  manager.placeOnMonitor(status.id, monitor.index, "bottom-right", 16, 16);
+}
+
+console.log(`Opened ${manager.windowCount} windows across ${monitors.length} monitors`);
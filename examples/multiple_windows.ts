@@ -10,9 +10,9 @@ manager.start();
 // Create multiple windows
 console.log("Creating multiple windows...");
 
-const window1 = Number(manager.createWindowWithPosition(300, 300, "Window 1 - Red", 100, 100));
-const window2 = Number(manager.createWindowWithPosition(300, 300, "Window 2 - Green", 450, 100));
-const window3 = Number(manager.createWindowWithPosition(300, 300, "Window 3 - Blue", 800, 100));
+const window1 = manager.createWindowWithPosition(300, 300, "Window 1 - Red", 100, 100).id;
+const window2 = manager.createWindowWithPosition(300, 300, "Window 2 - Green", 450, 100).id;
+const window3 = manager.createWindowWithPosition(300, 300, "Window 3 - Blue", 800, 100).id;
 
 console.log(`Created windows: ${window1}, ${window2}, ${window3}`);
 console.log(`Total windows: ${manager.windowCount}`);
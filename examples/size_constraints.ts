@@ -0,0 +1,32 @@
+import { WindowManager } from "../index";
+
+// Demo: Size limits, resize increments and aspect ratio locking
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+
+// Start the event loop
+manager.start();
+
+// Limits can be given when the window is created...
+const video = manager.createWindowWithOptions(640, 360, "16:9 Video", {
+  x: 100,
+  y: 100,
+  minSize: { width: 320, height: 180 },
+  maxSize: { width: 1280, height: 720 },
+  aspectRatio: { width: 16, height: 9 },
+});
+video.clear(20, 20, 20);
+video.present();
+
+// ...or changed later; a terminal-like window resized in 8x16 character cells
+const terminal = manager.createWindow(648, 400, "Terminal");
+manager.setMinSize(terminal.id, 200, 100);
+manager.setResizeIncrements(terminal.id, 8, 16);
+terminal.clear(0, 43, 54);
+terminal.present();
+
+// The drawing size follows resizes, clamped to the limits
+setInterval(() => {
+  const size = manager.getSize(terminal.id);
+  console.log(`Terminal is ${size.width}x${size.height}`);
+}, 2000);
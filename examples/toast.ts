@@ -0,0 +1,34 @@
+import { WindowManager } from "../index";
+
+// Demo: A notification toast that fades in, stays for a few seconds and fades out
+console.log("Creating WindowManager...");
+const manager = new WindowManager();
+
+// Start the event loop
+manager.start();
+
+// A notification window that stays out of the taskbar and pager
+const toast = manager.createWindowWithOptions(280, 64, "Toast", {
+  alwaysOnTop: true,
+  transparent: true,
+  decorations: false,
+  windowType: "notification",
+  skipTaskbar: true,
+  skipPager: true,
+});
+toast.clear(32, 32, 40, 230);
+for (let x = 0; x < 280; x++) {
+  toast.setPixel(x, 0, 90, 160, 255);
+}
+toast.present();
+
+// Start invisible in the bottom-right corner, then fade in
+manager.placeOnMonitor(toast.id, 0, "bottom-right", 16, 16);
+toast.fadeIn(250);
+console.log("Toast shown");
+
+// Fade out after three seconds and close the window once it is invisible
+setTimeout(() => {
+  toast.fadeOut(400, true);
+  console.log("Toast fading out");
+}, 3000);
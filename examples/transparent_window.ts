@@ -7,14 +7,11 @@ const manager = new WindowManager();
 
 // Start the event loop
 manager.start();
-const glassWindow = Number(manager.createWindowWithOptions(
+const glassWindow = manager.createWindowWithOptions(
   300, 200,
   "5. Glass Effect",
-  400, 350,
-  false, // alwaysOnTop
-  false,  // transparent
-  true  // decorations
-));
+  { x: 400, y: 350 }
+).id;
 // Create a frosted glass effect with semi-transparent white
 drawGlassEffect(glassWindow);
 drawLabel(glassWindow, 50, 90, "Glass Effect!", 50, 50, 50);
@@ -31,6 +28,10 @@ const operationsInterval = setInterval(() => {
   step++;
 
   switch (step) {
+    case 1:
+      // Known once the event loop has created the window
+      console.log(`Supports transparency: ${manager.supportsTransparency(glassWindow)}`);
+      break;
 
     case 5:
       clearInterval(operationsInterval);
@@ -11,7 +11,7 @@ manager.start();
 console.log("\n=== Creating Windows with Advanced Features ===\n");
 
 // 1. Normal window (for comparison)
-const normalWindow = Number(manager.createWindowWithPosition(300, 200, "1. Normal Window", 50, 50));
+const normalWindow = manager.createWindowWithPosition(300, 200, "1. Normal Window", 50, 50).id;
 manager.clear(normalWindow, 50, 50, 50);
 drawLabel(normalWindow, 50, 90, "Normal Window", 255, 255, 255);
 drawLabel(normalWindow, 30, 120, "(Clickable)", 200, 200, 200);
@@ -19,14 +19,11 @@ manager.present(normalWindow);
 console.log(`1. Normal window created (ID: ${normalWindow})`);
 
 // 2. Always-on-top window
-const topWindow = Number(manager.createWindowWithOptions(
+const topWindow = manager.createWindowWithOptions(
   300, 200,
   "2. Always on Top",
-  400, 50,
-  true, // alwaysOnTop
-  true,
-  true
-));
+  { x: 400, y: 50, alwaysOnTop: true, transparent: true }
+).id;
 manager.clear(topWindow, 150, 0, 0);
 drawLabel(topWindow, 50, 90, "Always On Top!", 255, 255, 255);
 drawLabel(topWindow, 30, 120, "(Stays above others)", 200, 200, 200);
@@ -34,7 +31,7 @@ manager.present(topWindow);
 console.log(`2. Always-on-top window created (ID: ${topWindow})`);
 
 // 3. Window that will move around
-const movingWindow = Number(manager.createWindowWithPosition(300, 200, "3. Moving Window", 750, 50));
+const movingWindow = manager.createWindowWithPosition(300, 200, "3. Moving Window", 750, 50).id;
 manager.clear(movingWindow, 0, 100, 0);
 drawLabel(movingWindow, 50, 90, "Watch Me Move!", 255, 255, 255);
 drawLabel(movingWindow, 30, 120, "(Auto-repositioning)", 200, 200, 200);
@@ -42,7 +39,7 @@ manager.present(movingWindow);
 console.log(`3. Moving window created (ID: ${movingWindow}) at (750, 50)`);
 
 // 4. Click-through window (ignores input)
-const clickThroughWindow = Number(manager.createWindowWithPosition(300, 200, "4. Click-Through", 50, 350));
+const clickThroughWindow = manager.createWindowWithPosition(300, 200, "4. Click-Through", 50, 350).id;
 manager.clear(clickThroughWindow, 0, 50, 150);
 drawLabel(clickThroughWindow, 50, 90, "Click-Through!", 255, 255, 255);
 drawLabel(clickThroughWindow, 20, 120, "(Clicks pass through)", 200, 200, 200);
@@ -52,7 +49,7 @@ manager.setIgnoreInput(clickThroughWindow, true);
 console.log(`4. Click-through window created (ID: ${clickThroughWindow}) - Input ignored`);
 
 // 5. Toggle window (will toggle features)
-const toggleWindow = Number(manager.createWindowWithPosition(300, 200, "5. Toggle Features", 400, 350));
+const toggleWindow = manager.createWindowWithPosition(300, 200, "5. Toggle Features", 400, 350).id;
 manager.clear(toggleWindow, 100, 0, 100);
 drawLabel(toggleWindow, 50, 90, "Feature Toggle", 255, 255, 255);
 drawLabel(toggleWindow, 30, 120, "(See console)", 200, 200, 200);
@@ -11,54 +11,51 @@ manager.start();
 console.log("\n=== Creating Windows with Different Options ===\n");
 
 // 1. Basic window (default position)
-const basicWindow = Number(manager.createWindow(300, 200, "1. Basic Window"));
+const basicWindow = manager.createWindow(300, 200, "1. Basic Window").id;
 manager.clear(basicWindow, 100, 100, 100);
 drawLabel(basicWindow, 50, 100, "Default Position", 255, 255, 255);
 manager.present(basicWindow);
 console.log(`1. Basic window created (ID: ${basicWindow})`);
 
 // 2. Window with specific position
-const positionedWindow = Number(manager.createWindowWithPosition(
+const positionedWindow = manager.createWindowWithPosition(
   300, 200, 
   "2. Positioned Window", 
   350, 100
-));
+).id;
 manager.clear(positionedWindow, 0, 100, 150);
 drawLabel(positionedWindow, 50, 100, "Position: 350,100", 255, 255, 255);
 manager.present(positionedWindow);
 console.log(`2. Positioned window created (ID: ${positionedWindow}) at (350, 100)`);
 
 // 3. Always-on-top window
-const topWindow = Number(manager.createWindowWithOptions(
+const topWindow = manager.createWindowWithOptions(
   300, 200,
   "3. Always on Top",
-  700, 100,
-  true,  // alwaysOnTop
-  false, // transparent
-  true   // decorations
-));
+  { x: 700, y: 100, alwaysOnTop: true }
+).id;
 manager.clear(topWindow, 150, 0, 0);
 drawLabel(topWindow, 50, 100, "Always On Top!", 255, 255, 255);
 manager.present(topWindow);
 console.log(`3. Always-on-top window created (ID: ${topWindow})`);
 
 // 4. Window that we'll move dynamically
-const movableWindow = Number(manager.createWindowWithPosition(
+const movableWindow = manager.createWindowWithPosition(
   300, 200,
   "4. Moving Window",
   100, 350
-));
+).id;
 manager.clear(movableWindow, 0, 100, 0);
 drawLabel(movableWindow, 50, 100, "Watch me move!", 255, 255, 255);
 manager.present(movableWindow);
 console.log(`4. Movable window created (ID: ${movableWindow}) at (100, 350)`);
 
 // 5. Window with dynamic title
-const titleWindow = Number(manager.createWindowWithPosition(
+const titleWindow = manager.createWindowWithPosition(
   300, 200,
   "5. Dynamic Title - Initial",
   450, 350
-));
+).id;
 manager.clear(titleWindow, 100, 0, 100);
 drawLabel(titleWindow, 50, 100, "Title changes...", 255, 255, 255);
 manager.present(titleWindow);
@@ -2,23 +2,42 @@ use napi::bindgen_prelude::*;
 use napi::JsNumber;
 use napi_derive::napi;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use winit::event_loop::EventLoop;
+use std::time::Duration;
 
+use winit::event_loop::EventLoopBuilder;
 #[cfg(target_os = "linux")]
 use winit::platform::x11::EventLoopBuilderExtX11;
-use winit::event_loop::EventLoopBuilder;
 
 use crate::renderer::window_manager::app::WindowManagerApp;
+use crate::renderer::window_manager::blend;
+use crate::renderer::window_manager::clipboard::{Clipboard, ClipboardConnection};
+use crate::renderer::window_manager::command_buffer::{self, BatchCommand, DrawOp};
+use crate::renderer::window_manager::command_queue::{
+    command_queue, CommandReceiver, CommandSender,
+};
 use crate::renderer::window_manager::types::*;
-use crate::renderer::window_manager::utils::js_number_to_u64;
+use crate::renderer::window_manager::utils::{
+    check_size_constraints, parse_blend_mode, parse_color_format, parse_cursor_grab_mode,
+    parse_cursor_icon, parse_fullscreen_mode, parse_icon, parse_monitor_anchor, parse_opacity,
+    parse_optional_size, parse_size_limit, parse_user_attention, parse_window_hints,
+    parse_window_id,
+};
+use crate::renderer::window_manager::window::Window;
+use crate::renderer::window_manager::x11_utils;
+
+/// Largest custom cursor accepted; X servers reject bigger cursor images anyway
+const MAX_CURSOR_SIZE: u32 = 256;
 
 /// Window Manager that handles multiple windows in a non-blocking way
 #[napi]
 pub struct WindowManager {
     pub(crate) state: SharedState,
+    pub(crate) commands: CommandSender,
+    pub(crate) command_receiver: Option<CommandReceiver>,
     pub(crate) next_window_id: Arc<Mutex<u64>>,
+    pub(crate) clipboard: Arc<ClipboardConnection>,
     pub(crate) _event_loop_handle: Option<thread::JoinHandle<()>>,
 }
 
@@ -29,54 +48,92 @@ impl WindowManager {
     pub fn new() -> Result<Self> {
         let state = Arc::new(Mutex::new(WindowManagerState {
             windows: HashMap::new(),
-            pending_commands: Vec::new(),
+            frame_requests: Vec::new(),
+            next_frame_request_id: 1,
             should_exit: false,
+            monitors: Vec::new(),
         }));
 
+        let (commands, command_receiver) = command_queue();
         let next_window_id = Arc::new(Mutex::new(1u64));
 
         Ok(Self {
             state,
+            commands,
+            command_receiver: Some(command_receiver),
             next_window_id,
+            clipboard: Arc::default(),
             _event_loop_handle: None,
         })
     }
 
+    /// Clipboard and primary selection access; works with or without open windows
+    #[napi(getter)]
+    pub fn clipboard(&self) -> Clipboard {
+        Clipboard::new(self.clipboard.clone())
+    }
+
     /// Start the event loop (must be called before creating windows)
     #[napi]
     pub fn start(&mut self) -> Result<()> {
         let state = self.state.clone();
+        let commands = self.command_receiver.take().ok_or_else(|| {
+            napi::Error::new(
+                napi::Status::GenericFailure,
+                "Event loop has already been started",
+            )
+        })?;
+        let waker = self.commands.waker().clone();
+        let (ready, started) = mpsc::channel();
 
         let handle = thread::spawn(move || {
             // Create event loop with any_thread flag for Linux
             #[cfg(target_os = "linux")]
-            let event_loop = EventLoopBuilder::new()
+            let event_loop = EventLoopBuilder::<UserEvent>::with_user_event()
                 .with_any_thread(true)
                 .build()
                 .expect("Failed to create event loop");
 
             #[cfg(not(target_os = "linux"))]
-            let event_loop = EventLoop::new().expect("Failed to create event loop");
+            let event_loop = EventLoopBuilder::<UserEvent>::with_user_event()
+                .build()
+                .expect("Failed to create event loop");
 
-            let mut app = WindowManagerApp::new(state);
+            // Commands queued from JS wake the loop through this proxy
+            waker.set_proxy(event_loop.create_proxy());
+
+            let mut app = WindowManagerApp::new(state, commands);
+            app.refresh_monitors(&event_loop);
+            let _ = ready.send(());
             let _ = event_loop.run(move |event, event_loop| {
                 // winit 0.29 run takes 2 arguments: event and event_loop
                 // We need to adapt this to our 3-argument handler
                 use winit::event_loop::ControlFlow;
-                
+
                 let mut control_flow = ControlFlow::Wait;
                 app.handle_event(event, event_loop, &mut control_flow);
+                event_loop.set_control_flow(control_flow);
             });
         });
 
         self._event_loop_handle = Some(handle);
-        Ok(())
+
+        // Wait for the first monitor list so `getMonitors` is right from the start
+        started.recv().map_err(|_| {
+            napi::Error::new(napi::Status::GenericFailure, "Event loop failed to start")
+        })
     }
 
-    /// Create a new window and return its ID
+    /// Create a new window, optionally with any of the settings in `WindowOptions`
     #[napi]
-    pub fn create_window(&mut self, width: u32, height: u32, title: String) -> Result<u64> {
-        self.create_window_with_options(width, height, title, None, None, false, false, true)
+    pub fn create_window(
+        &mut self,
+        width: u32,
+        height: u32,
+        title: String,
+        options: Option<WindowOptions>,
+    ) -> Result<Window> {
+        self.create_window_with_options(width, height, title, options.unwrap_or_default())
     }
 
     /// Create a new window with position options
@@ -88,30 +145,51 @@ impl WindowManager {
         title: String,
         x: i32,
         y: i32,
-    ) -> Result<u64> {
-        self.create_window_with_options(width, height, title, Some(x), Some(y), false, false, true)
+    ) -> Result<Window> {
+        let options = WindowOptions {
+            x: Some(x),
+            y: Some(y),
+            ..Default::default()
+        };
+        self.create_window_with_options(width, height, title, options)
     }
 
-    /// Create a new window with all options
+    /// Create a new window with options: position, always-on-top, transparency,
+    /// decorations, color format, window type, taskbar and pager visibility, parent and
+    /// modality, `WM_CLASS` and size limits. See `WindowOptions`
     #[napi]
-    #[allow(clippy::too_many_arguments)]
     pub fn create_window_with_options(
         &mut self,
         width: u32,
         height: u32,
         title: String,
-        x: Option<i32>,
-        y: Option<i32>,
-        always_on_top: bool,
-        transparent: bool,
-        decorations: bool,
-    ) -> Result<u64> {
+        options: WindowOptions,
+    ) -> Result<Window> {
+        let (x, y) = (options.x, options.y);
+        let always_on_top = options.always_on_top.unwrap_or(false);
+        let transparent = options.transparent.unwrap_or(false);
+        let decorations = options.decorations.unwrap_or(true);
+        let color_format = match options.color_format.as_deref() {
+            Some(format) => parse_color_format(format)?,
+            None => ColorFormat::default(),
+        };
+        let hints = parse_window_hints(options)?;
+        if let Some(parent) = hints.parent {
+            self.ensure_window(parent)?;
+        }
+        let (width, height) = hints.size_constraints.clamp(width, height);
         let id = {
             let mut counter = self
                 .next_window_id
                 .lock()
                 .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
             let id = *counter;
+            if id > u32::MAX as u64 {
+                return Err(napi::Error::new(
+                    napi::Status::GenericFailure,
+                    "No window ids left",
+                ));
+            }
             *counter += 1;
             id
         };
@@ -122,34 +200,37 @@ impl WindowManager {
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
         // Pre-register the window in shared state so window_count() and window_exists() work immediately
-        let pixel_count = (width * height) as usize;
-        // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
-        // For transparent windows, initialize with fully transparent pixels (alpha = 0)
-        // For opaque windows, initialize with opaque black (alpha = 255)
-        let pixel_buffer = if transparent {
-            vec![0x00000000u32; pixel_count] // Fully transparent ARGB (A=0, R=0, G=0, B=0)
-        } else {
-            vec![0xFF000000u32; pixel_count] // Opaque black ARGB (A=255, R=0, G=0, B=0)
-        };
+        // For transparent windows the buffer starts fully transparent, otherwise opaque black
+        let framebuffer = Framebuffer::shared(width, height, transparent);
 
         state.windows.insert(
             id,
             WindowState {
                 width,
                 height,
-                pixel_buffer,
+                framebuffer,
                 needs_redraw: true,
                 title: title.clone(),
                 x,
                 y,
                 always_on_top,
                 transparent,
+                supports_transparency: false,
                 decorations,
+                alpha_hit_threshold: None,
+                ignore_input: false,
+                color_format,
+                opacity: 1.0,
+                parent: hints.parent,
+                blocks_parent_input: hints.block_parent_input,
+                size_constraints: hints.size_constraints,
+                stats: FrameStats::default(),
+                native: NativeWindowState::requested(width, height),
                 winit_id: None, // Will be set when window is actually created
             },
         );
 
-        state.pending_commands.push(WindowCommand::CreateWindow {
+        self.commands.send(WindowCommand::CreateWindow {
             id,
             width,
             height,
@@ -159,14 +240,17 @@ impl WindowManager {
             always_on_top,
             transparent,
             decorations,
+            hints,
         });
 
-        Ok(id)
+        Ok(Window::new(id, &self.state, self.commands.clone()))
     }
 
     /// Set a pixel in a window's buffer
     /// Sets the pixel to opaque (alpha = 255) with the given RGB values
+    /// `blendMode` (e.g. "multiply") composites onto the pixel instead of replacing it
     #[napi]
+    #[allow(clippy::too_many_arguments)]
     pub fn set_pixel(
         &self,
         window_id: JsNumber,
@@ -175,30 +259,22 @@ impl WindowManager {
         r: u8,
         g: u8,
         b: u8,
+        blend_mode: Option<String>,
     ) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
+        let mode = parse_blend_mode(blend_mode.as_deref())?;
         // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
         // Alpha is in the high byte (0xFF = fully opaque)
-        let color = ((0xFF as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+        let color = (0xFFu32 << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
 
-        let mut state = self
-            .state
-            .lock()
-            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
-
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            if x < window_state.width && y < window_state.height {
-                let index = (y * window_state.width + x) as usize;
-                window_state.pixel_buffer[index] = color;
-                window_state.needs_redraw = true;
-            }
-        }
-
-        Ok(())
+        self.write_framebuffer(window_id, |framebuffer, _| {
+            framebuffer.set_logical_pixel(x, y, color, mode)
+        })
     }
 
     /// Set a pixel with alpha (RGBA) in a window's buffer
     /// The alpha value controls transparency: 0 = fully transparent, 255 = fully opaque
+    /// Without a `blendMode` the pixel is replaced; pass "source-over" to draw over it
     #[napi]
     #[allow(clippy::too_many_arguments)]
     pub fn set_pixel_rgba(
@@ -210,133 +286,346 @@ impl WindowManager {
         g: u8,
         b: u8,
         a: u8,
+        blend_mode: Option<String>,
     ) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
+        let mode = parse_blend_mode(blend_mode.as_deref())?;
         // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
         // Alpha is in the high byte (bits 24-31)
         let color = ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
 
-        let mut state = self
-            .state
-            .lock()
-            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
-
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            if x < window_state.width && y < window_state.height {
-                let index = (y * window_state.width + x) as usize;
-                window_state.pixel_buffer[index] = color;
-                window_state.needs_redraw = true;
-            }
-        }
-
-        Ok(())
+        self.write_framebuffer(window_id, |framebuffer, format| {
+            framebuffer.set_logical_pixel(x, y, format.to_straight(color), mode)
+        })
     }
 
     /// Clear a window's buffer to a color with optional alpha
     /// If alpha is not provided, defaults to 255 (fully opaque)
     #[napi]
     pub fn clear(&self, window_id: JsNumber, r: u8, g: u8, b: u8, a: Option<u8>) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
         // Alpha defaults to 255 (fully opaque) if not provided
         let alpha = a.unwrap_or(0xFF);
         let color = ((alpha as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
 
+        self.write_framebuffer(window_id, |framebuffer, format| {
+            framebuffer.fill(format.to_straight(color))
+        })
+    }
+
+    /// Set how colors passed to the pixel APIs and `submit` are encoded: "straight" (the
+    /// default), or "premultiplied" when the color channels are already multiplied by alpha
+    #[napi]
+    pub fn set_color_format(&self, window_id: JsNumber, format: String) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let format = parse_color_format(&format)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            for pixel in &mut window_state.pixel_buffer {
-                *pixel = color;
-            }
-            window_state.needs_redraw = true;
+        state.window_mut(window_id)?.color_format = format;
+        Ok(())
+    }
+
+    /// Set whole-window opacity from 0.0 (invisible) to 1.0 (opaque), on top of per-pixel
+    /// alpha. Needs a compositing manager; cancels a running fade
+    #[napi]
+    pub fn set_opacity(&self, window_id: JsNumber, opacity: f64) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let opacity = parse_opacity(opacity)?;
+        {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+            state.window_mut(window_id)?.opacity = opacity;
+        }
+
+        self.commands
+            .send(WindowCommand::SetOpacity { window_id, opacity });
+        Ok(())
+    }
+
+    /// Show or hide a window in the taskbar
+    #[napi]
+    pub fn set_skip_taskbar(&self, window_id: JsNumber, skip: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands
+            .send(WindowCommand::SetSkipTaskbar { window_id, skip });
+        Ok(())
+    }
+
+    /// Show or hide a window in pagers and workspace switchers
+    #[napi]
+    pub fn set_skip_pager(&self, window_id: JsNumber, skip: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands
+            .send(WindowCommand::SetSkipPager { window_id, skip });
+        Ok(())
+    }
+
+    /// Set the icon shown in the title bar and taskbar from straight RGBA bytes,
+    /// `width * height * 4` of them
+    #[napi]
+    pub fn set_icon(
+        &self,
+        window_id: JsNumber,
+        rgba: Uint8Array,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+        let icon = parse_icon(&rgba, width, height)?;
+
+        self.commands
+            .send(WindowCommand::SetIcon { window_id, icon });
+        Ok(())
+    }
+
+    /// Current whole-window opacity, following fades as they run
+    #[napi]
+    pub fn get_opacity(&self, window_id: JsNumber) -> Result<f64> {
+        self.read_window(window_id, |window_state| window_state.opacity)
+    }
+
+    /// Fade a window in from fully transparent to opaque; `durationMs` defaults to 200
+    #[napi]
+    pub fn fade_in(&self, window_id: JsNumber, duration_ms: Option<u32>) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+            state.window_mut(window_id)?.opacity = 0.0;
         }
 
+        self.commands.send(WindowCommand::SetOpacity {
+            window_id,
+            opacity: 0.0,
+        });
+        self.commands.send(WindowCommand::FadeOpacity {
+            window_id,
+            to: 1.0,
+            duration: Duration::from_millis(duration_ms.unwrap_or(DEFAULT_FADE_MS) as u64),
+            close_when_done: false,
+        });
+        Ok(())
+    }
+
+    /// Fade a window out to fully transparent; `durationMs` defaults to 200
+    /// With `closeWhenDone` the window is closed once the fade finishes, otherwise it stays
+    /// open (and still receives input) at opacity 0
+    #[napi]
+    pub fn fade_out(
+        &self,
+        window_id: JsNumber,
+        duration_ms: Option<u32>,
+        close_when_done: Option<bool>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::FadeOpacity {
+            window_id,
+            to: 0.0,
+            duration: Duration::from_millis(duration_ms.unwrap_or(DEFAULT_FADE_MS) as u64),
+            close_when_done: close_when_done.unwrap_or(false),
+        });
         Ok(())
     }
 
     /// Clear a window's buffer to black
     #[napi]
     pub fn clear_black(&self, window_id: JsNumber) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         self.clear_inner(window_id, 0, 0, 0)
     }
 
     fn clear_inner(&self, window_id: u64, r: u8, g: u8, b: u8) -> Result<()> {
         // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
         // clear_black always uses opaque black
-        let color = ((0xFF as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+        let color = (0xFFu32 << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+
+        self.write_framebuffer(window_id, |framebuffer, _| framebuffer.fill(color))
+    }
+
+    /// Write to a window's pixels; they reach the screen on the next `present` or `submit`,
+    /// so the event loop never draws a half-written frame
+    /// Only the metadata lock is held for the lookup; pixels are written under the
+    /// framebuffer's own lock. The writer gets the window's color format
+    fn write_framebuffer(
+        &self,
+        window_id: u64,
+        write: impl FnOnce(&mut Framebuffer, ColorFormat),
+    ) -> Result<()> {
+        let (framebuffer, color_format) = {
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+            let window_state = state.window(window_id)?;
+            (window_state.framebuffer.clone(), window_state.color_format)
+        };
+
+        let mut framebuffer = framebuffer
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+        write(&mut framebuffer, color_format);
+        Ok(())
+    }
 
+    /// Read from a window's state under the metadata lock
+    fn read_window<R>(
+        &self,
+        window_id: JsNumber,
+        read: impl FnOnce(&WindowState) -> R,
+    ) -> Result<R> {
+        let window_id = parse_window_id(window_id)?;
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        Ok(read(state.window(window_id)?))
+    }
+
+    /// Fail with a clear error unless the window exists
+    fn ensure_window(&self, window_id: u64) -> Result<()> {
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        state.window(window_id).map(|_| ())
+    }
+
+    /// Change one of a window's size limits and apply the result to the native window
+    fn update_size_constraints(
+        &self,
+        window_id: JsNumber,
+        update: impl FnOnce(&mut SizeConstraints),
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            for pixel in &mut window_state.pixel_buffer {
-                *pixel = color;
-            }
-            window_state.needs_redraw = true;
-        }
+        let window_state = state.window_mut(window_id)?;
+        let mut constraints = window_state.size_constraints;
+        update(&mut constraints);
+        window_state.size_constraints = check_size_constraints(constraints)?;
 
+        self.commands.send(WindowCommand::SetSizeConstraints {
+            window_id,
+            constraints,
+        });
         Ok(())
     }
 
-    /// Present/render a window's buffer
+    /// Present/render a window's buffer; pixel writes stay off screen until the next present
     #[napi]
     pub fn present(&self, window_id: JsNumber) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            window_state.needs_redraw = true;
+        let window_state = state.window_mut(window_id)?;
+        window_state.needs_redraw = true;
+        window_state.stats.request_present();
+
+        self.commands.send(WindowCommand::Present { window_id });
+        Ok(())
+    }
+
+    /// Submit a batch of drawing and window commands in one call.
+    /// Accepts an array of command objects or a binary command buffer (see
+    /// `docs/command_buffer.md`); a buffer JS reuses between frames can be passed as a
+    /// `Uint8Array` view over the written part. The batch is validated up front and applied
+    /// on the event loop under a single lock
+    #[napi(ts_args_type = "windowId: number, commands: BatchCommand[] | Uint8Array")]
+    pub fn submit(
+        &self,
+        window_id: JsNumber,
+        commands: Either<Vec<BatchCommand>, Uint8Array>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let mut ops = match commands {
+            Either::A(objects) => command_buffer::decode_objects(&objects)?,
+            Either::B(bytes) => command_buffer::decode_buffer(&bytes)?,
+        };
+
+        {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+            let window_state = state.window_mut(window_id)?;
+            if window_state.color_format == ColorFormat::Premultiplied {
+                for op in &mut ops {
+                    op.map_colors(blend::unpremultiply);
+                }
+            }
+            for op in &ops {
+                match op {
+                    DrawOp::Present => window_state.stats.request_present(),
+                    DrawOp::SetPosition { x, y } => {
+                        window_state.x = Some(*x);
+                        window_state.y = Some(*y);
+                    }
+                    DrawOp::SetTitle { title } => window_state.title = title.clone(),
+                    _ => {}
+                }
+            }
         }
 
-        state
-            .pending_commands
-            .push(WindowCommand::Present { window_id });
+        self.commands.send(WindowCommand::Submit { window_id, ops });
         Ok(())
     }
 
     /// Set window position
     #[napi]
     pub fn set_position(&self, window_id: JsNumber, x: i32, y: i32) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            window_state.x = Some(x);
-            window_state.y = Some(y);
-        }
+        let window_state = state.window_mut(window_id)?;
+        window_state.x = Some(x);
+        window_state.y = Some(y);
 
-        state
-            .pending_commands
-            .push(WindowCommand::SetPosition { window_id, x, y });
+        self.commands
+            .send(WindowCommand::SetPosition { window_id, x, y });
         Ok(())
     }
 
     /// Set always on top
     #[napi]
     pub fn set_always_on_top(&self, window_id: JsNumber, always_on_top: bool) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            window_state.always_on_top = always_on_top;
-        }
+        let window_state = state.window_mut(window_id)?;
+        window_state.always_on_top = always_on_top;
 
-        state.pending_commands.push(WindowCommand::SetAlwaysOnTop {
+        self.commands.send(WindowCommand::SetAlwaysOnTop {
             window_id,
             always_on_top,
         });
@@ -346,56 +635,687 @@ impl WindowManager {
     /// Set window title
     #[napi]
     pub fn set_title(&self, window_id: JsNumber, title: String) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        if let Some(window_state) = state.windows.get_mut(&window_id) {
-            window_state.title = title.clone();
-        }
+        let window_state = state.window_mut(window_id)?;
+        window_state.title = title.clone();
 
-        state
-            .pending_commands
-            .push(WindowCommand::SetTitle { window_id, title });
+        self.commands
+            .send(WindowCommand::SetTitle { window_id, title });
         Ok(())
     }
 
     /// Set window to ignore mouse/keyboard input (click-through)
     #[napi]
     pub fn set_ignore_input(&self, window_id: JsNumber, ignore: bool) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands
+            .send(WindowCommand::SetIgnoreInput { window_id, ignore });
+        Ok(())
+    }
+
+    /// Minimize (iconify) a window, or restore it with `false`
+    #[napi]
+    pub fn set_minimized(&self, window_id: JsNumber, minimized: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::SetMinimized {
+            window_id,
+            minimized,
+        });
+        Ok(())
+    }
+
+    /// Maximize a window, or restore its previous size with `false`
+    #[napi]
+    pub fn set_maximized(&self, window_id: JsNumber, maximized: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::SetMaximized {
+            window_id,
+            maximized,
+        });
+        Ok(())
+    }
+
+    /// Make a window fullscreen: "borderless" covers the monitor, "exclusive" also switches
+    /// the monitor to its largest video mode; null leaves fullscreen. `monitor` is an index
+    /// into the available monitors and defaults to the one the window is on
+    #[napi]
+    pub fn set_fullscreen(
+        &self,
+        window_id: JsNumber,
+        mode: Option<String>,
+        monitor: Option<u32>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let mode = mode.as_deref().map(parse_fullscreen_mode).transpose()?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::SetFullscreen {
+            window_id,
+            mode,
+            monitor,
+        });
+        Ok(())
+    }
+
+    /// Show or hide a window
+    #[napi]
+    pub fn set_visible(&self, window_id: JsNumber, visible: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands
+            .send(WindowCommand::SetVisible { window_id, visible });
+        Ok(())
+    }
+
+    /// Raise a window and give it input focus
+    /// The window manager may refuse to steal focus; use `requestUserAttention` then
+    #[napi]
+    pub fn focus(&self, window_id: JsNumber) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::Focus { window_id });
+        Ok(())
+    }
+
+    /// Ask for the user's attention, e.g. by flashing the taskbar entry:
+    /// "critical", "informational" (default) or "none" to cancel a previous request
+    #[napi]
+    pub fn request_user_attention(
+        &self,
+        window_id: JsNumber,
+        attention: Option<String>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let attention = parse_user_attention(attention.as_deref().unwrap_or("informational"))?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::RequestUserAttention {
+            window_id,
+            attention,
+        });
+        Ok(())
+    }
+
+    /// Allow or prevent resizing a window by the user
+    #[napi]
+    pub fn set_resizable(&self, window_id: JsNumber, resizable: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::SetResizable {
+            window_id,
+            resizable,
+        });
+        Ok(())
+    }
+
+    /// Set the smallest size the user can resize a window to; null removes the limit
+    #[napi]
+    pub fn set_min_size(
+        &self,
+        window_id: JsNumber,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<()> {
+        let min_size = parse_size_limit("Minimum size", parse_optional_size(width, height)?)?;
+        self.update_size_constraints(window_id, |constraints| constraints.min_size = min_size)
+    }
+
+    /// Set the largest size the user can resize a window to; null removes the limit
+    #[napi]
+    pub fn set_max_size(
+        &self,
+        window_id: JsNumber,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<()> {
+        let max_size = parse_size_limit("Maximum size", parse_optional_size(width, height)?)?;
+        self.update_size_constraints(window_id, |constraints| constraints.max_size = max_size)
+    }
+
+    /// Resize a window in steps of `width` x `height` from its minimum size, e.g. a
+    /// terminal's character cell; null resizes freely again
+    #[napi]
+    pub fn set_resize_increments(
+        &self,
+        window_id: JsNumber,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<()> {
+        let increments =
+            parse_size_limit("Resize increments", parse_optional_size(width, height)?)?;
+        self.update_size_constraints(window_id, |constraints| {
+            constraints.resize_increments = increments
+        })
+    }
+
+    /// Lock a window to a `width` to `height` ratio, e.g. 16 and 9; null unlocks it
+    #[napi]
+    pub fn set_aspect_ratio(
+        &self,
+        window_id: JsNumber,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<()> {
+        let aspect_ratio = parse_size_limit("Aspect ratio", parse_optional_size(width, height)?)?;
+        self.update_size_constraints(window_id, |constraints| {
+            constraints.aspect_ratio = aspect_ratio
+        })
+    }
+
+    /// Show or hide a window's title bar and borders
+    #[napi]
+    pub fn set_decorations(&self, window_id: JsNumber, decorations: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        state.window_mut(window_id)?.decorations = decorations;
+
+        self.commands.send(WindowCommand::SetDecorations {
+            window_id,
+            decorations,
+        });
+        Ok(())
+    }
+
+    /// List the connected monitors with their position, size, work area, scale factor,
+    /// refresh rate and whether they are the primary monitor (all in physical pixels)
+    #[napi]
+    pub fn get_monitors(&self) -> Result<Vec<MonitorInfo>> {
+        if self.command_receiver.is_some() {
+            return Err(napi::Error::new(
+                napi::Status::GenericFailure,
+                "Event loop has not been started",
+            ));
+        }
+
+        // Monitors can only be queried on the event loop thread, which keeps this list
+        // up to date
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+        Ok(state.monitors.clone())
+    }
+
+    /// Center a window in a monitor's work area; defaults to the monitor the window is on
+    #[napi]
+    pub fn center_on_monitor(&self, window_id: JsNumber, monitor: Option<u32>) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::PlaceOnMonitor {
+            window_id,
+            monitor,
+            anchor: MonitorAnchor::Center,
+            offset_x: 0,
+            offset_y: 0,
+        });
+        Ok(())
+    }
+
+    /// Place a window relative to a monitor's work area: `anchor` is "top-left", "top",
+    /// "top-right", "left", "center", "right", "bottom-left", "bottom" or "bottom-right",
+    /// and the optional margins (logical pixels) push the window inwards from that edge
+    #[napi]
+    pub fn place_on_monitor(
+        &self,
+        window_id: JsNumber,
+        monitor: u32,
+        anchor: String,
+        margin_x: Option<i32>,
+        margin_y: Option<i32>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let anchor = parse_monitor_anchor(&anchor)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::PlaceOnMonitor {
+            window_id,
+            monitor: Some(monitor),
+            anchor,
+            offset_x: margin_x.unwrap_or(0),
+            offset_y: margin_y.unwrap_or(0),
+        });
+        Ok(())
+    }
+
+    /// Give a window a non-rectangular shape (best used with `decorations: false`)
+    /// Accepts either a mask buffer of width * height bytes (non-zero = inside the shape)
+    /// or an array of `{ x, y, width, height }` rectangles in logical window pixels
+    #[napi]
+    pub fn set_shape(
+        &self,
+        window_id: JsNumber,
+        shape: Either<Buffer, Vec<ShapeRect>>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        let window_state = state.window(window_id)?;
+
+        let rects = match shape {
+            Either::A(mask) => {
+                let (width, height) = (window_state.width, window_state.height);
+                if mask.len() != width as usize * height as usize {
+                    return Err(napi::Error::new(
+                        napi::Status::InvalidArg,
+                        format!(
+                            "Shape mask must be width * height bytes ({}x{}), got {}",
+                            width,
+                            height,
+                            mask.len()
+                        ),
+                    ));
+                }
+                x11_utils::mask_rects(width, height, |i| mask[i] != 0)
+            }
+            Either::B(rects) => rects,
+        };
+
+        // Shapes are given in logical pixels but applied to the physical window
+        let scale_factor = window_state
+            .framebuffer
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?
+            .scale_factor;
+        let rects = x11_utils::scale_rects(&rects, scale_factor);
+
+        self.commands.send(WindowCommand::SetShape {
+            window_id,
+            rects: Some(rects),
+        });
+        Ok(())
+    }
+
+    /// Shape a window from the alpha channel of its current pixel buffer
+    /// Pixels with alpha at or above `threshold` (default 1) are kept, the rest are cut away
+    #[napi]
+    pub fn set_shape_from_alpha(&self, window_id: JsNumber, threshold: Option<u8>) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let framebuffer = {
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+            state.window(window_id)?.framebuffer.clone()
+        };
+
+        let rects = {
+            let framebuffer = framebuffer
+                .lock()
+                .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+            x11_utils::alpha_mask_rects(
+                &framebuffer.pixels,
+                framebuffer.width,
+                framebuffer.height,
+                threshold.unwrap_or(1),
+            )
+        };
+
+        self.commands.send(WindowCommand::SetShape {
+            window_id,
+            rects: Some(rects),
+        });
+        Ok(())
+    }
+
+    /// Restore a window's normal rectangular shape
+    #[napi]
+    pub fn clear_shape(&self, window_id: JsNumber) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::SetShape {
+            window_id,
+            rects: None,
+        });
+        Ok(())
+    }
+
+    /// Let input pass through transparent areas of a window
+    /// Pixels with alpha below `threshold` (default 1, i.e. fully transparent) become
+    /// click-through; the input region is recomputed from the pixel buffer on every present.
+    /// `setIgnoreInput(true)` takes precedence and keeps the whole window click-through
+    #[napi]
+    pub fn set_alpha_hit_test(
+        &self,
+        window_id: JsNumber,
+        enabled: bool,
+        threshold: Option<u8>,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        let threshold = if enabled {
+            Some(threshold.unwrap_or(1))
+        } else {
+            None
+        };
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        let window_state = state.window_mut(window_id)?;
+        window_state.alpha_hit_threshold = threshold;
+        window_state.needs_redraw = true;
+
+        self.commands.send(WindowCommand::SetAlphaHitTest {
+            window_id,
+            threshold,
+        });
+        Ok(())
+    }
+
+    /// Set the mouse cursor shown over a window using a CSS cursor name
+    /// (e.g. "default", "pointer", "text", "crosshair", "grab", "not-allowed")
+    #[napi]
+    pub fn set_cursor(&self, window_id: JsNumber, cursor: String) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+        let cursor = parse_cursor_icon(&cursor)?;
+
+        self.commands
+            .send(WindowCommand::SetCursorIcon { window_id, cursor });
+        Ok(())
+    }
+
+    /// Show or hide the mouse cursor while it is over a window
+    #[napi]
+    pub fn set_cursor_visible(&self, window_id: JsNumber, visible: bool) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands
+            .send(WindowCommand::SetCursorVisible { window_id, visible });
+        Ok(())
+    }
+
+    /// Grab the cursor: "none" releases it, "confined" keeps it inside the window,
+    /// "locked" pins it in place (useful for games and camera controls)
+    #[napi]
+    pub fn set_cursor_grab(&self, window_id: JsNumber, mode: String) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+        let mode = parse_cursor_grab_mode(&mode)?;
+
+        self.commands
+            .send(WindowCommand::SetCursorGrab { window_id, mode });
+        Ok(())
+    }
+
+    /// Set a custom cursor image from an RGBA buffer (4 bytes per pixel, straight alpha)
+    /// The hotspot is the pixel within the image that acts as the click point
+    #[napi]
+    pub fn set_custom_cursor(
+        &self,
+        window_id: JsNumber,
+        rgba: Buffer,
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    ) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        if width == 0 || height == 0 || width > MAX_CURSOR_SIZE || height > MAX_CURSOR_SIZE {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                format!(
+                    "Cursor size must be between 1x1 and {}x{}, got {}x{}",
+                    MAX_CURSOR_SIZE, MAX_CURSOR_SIZE, width, height
+                ),
+            ));
+        }
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if expected != Some(rgba.len()) {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                format!(
+                    "Cursor buffer must be width * height * 4 bytes ({}x{}), got {}",
+                    width,
+                    height,
+                    rgba.len()
+                ),
+            ));
+        }
+        if hotspot_x >= width || hotspot_y >= height {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                "Cursor hotspot must lie inside the image",
+            ));
+        }
+
+        self.commands.send(WindowCommand::SetCustomCursor {
+            window_id,
+            rgba: rgba.to_vec(),
+            width,
+            height,
+            hotspot_x,
+            hotspot_y,
+        });
+        Ok(())
+    }
+
+    /// Schedule a callback for the window's next frame, paced to the monitor refresh rate
+    /// The callback receives a timestamp (ms since the event loop started) and the time since
+    /// the previous frame in ms. Drawing and `present` calls made inside the callback are
+    /// applied in the same frame. Returns an id that can be passed to `cancelAnimationFrame`
+    #[napi]
+    pub fn request_animation_frame(
+        &self,
+        window_id: JsNumber,
+        #[napi(ts_arg_type = "(timestamp: number, delta: number) => void")] callback: FrameCallback,
+    ) -> Result<u32> {
+        let window_id = parse_window_id(window_id)?;
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        state.window(window_id)?;
+
+        let id = state.next_frame_request_id;
+        state.next_frame_request_id = state.next_frame_request_id.wrapping_add(1).max(1);
+        state.frame_requests.push(FrameRequest {
+            id,
+            window_id,
+            callback,
+        });
+        self.commands.wake();
+
+        Ok(id)
+    }
+
+    /// Cancel a callback scheduled with `requestAnimationFrame`
+    #[napi]
+    pub fn cancel_animation_frame(&self, request_id: u32) -> Result<()> {
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
         state
-            .pending_commands
-            .push(WindowCommand::SetIgnoreInput { window_id, ignore });
+            .frame_requests
+            .retain(|request| request.id != request_id);
         Ok(())
     }
 
-    /// Close a window
+    /// Close a window, along with any windows created with it as their parent
     #[napi]
     pub fn close_window(&self, window_id: JsNumber) -> Result<()> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
+        self.ensure_window(window_id)?;
+
+        self.commands.send(WindowCommand::CloseWindow { window_id });
+        Ok(())
+    }
+
+    /// Get presentation statistics for a window: frame counts, conversion and X11 upload
+    /// times (average and 99th percentile over recent frames) and bytes uploaded
+    #[napi]
+    pub fn get_stats(&self, window_id: JsNumber) -> Result<WindowStats> {
+        let window_id = parse_window_id(window_id)?;
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        let stats = &state.window(window_id)?.stats;
+        let (avg_convert_ms, p99_convert_ms) = frame_time_summary(&stats.convert_times);
+        let (avg_upload_ms, p99_upload_ms) = frame_time_summary(&stats.upload_times);
+
+        Ok(WindowStats {
+            presented_frames: stats.presented_frames as i64,
+            coalesced_presents: stats.coalesced_presents as i64,
+            bytes_uploaded: stats.bytes_uploaded as i64,
+            avg_convert_ms,
+            p99_convert_ms,
+            avg_upload_ms,
+            p99_upload_ms,
+            sample_count: stats.convert_times.len() as u32,
+        })
+    }
+
+    /// Reset a window's presentation statistics
+    #[napi]
+    pub fn reset_stats(&self, window_id: JsNumber) -> Result<()> {
+        let window_id = parse_window_id(window_id)?;
         let mut state = self
             .state
             .lock()
             .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
 
-        state
-            .pending_commands
-            .push(WindowCommand::CloseWindow { window_id });
+        let window_state = state.window_mut(window_id)?;
+        let present_pending = window_state.stats.present_pending;
+        window_state.stats = FrameStats {
+            present_pending,
+            ..FrameStats::default()
+        };
+
         Ok(())
     }
 
+    /// Get a window's outer position in logical pixels, as last reported by the window
+    /// system; null if the platform cannot report it
+    #[napi]
+    pub fn get_position(&self, window_id: JsNumber) -> Result<Option<WindowPosition>> {
+        self.read_window(window_id, |window_state| window_state.native.position())
+    }
+
+    /// Get a window's drawing size in logical pixels: the size passed to `createWindow`,
+    /// then the client area size clamped to the window's size limits as it is resized
+    #[napi]
+    pub fn get_size(&self, window_id: JsNumber) -> Result<WindowSize> {
+        self.read_window(window_id, |window_state| WindowSize {
+            width: window_state.width,
+            height: window_state.height,
+        })
+    }
+
+    /// Get the size of a window's pixel buffer in physical pixels, i.e. the logical size
+    /// times the scale factor
+    #[napi]
+    pub fn get_buffer_size(&self, window_id: JsNumber) -> Result<WindowSize> {
+        let framebuffer =
+            self.read_window(window_id, |window_state| window_state.framebuffer.clone())?;
+        let framebuffer = framebuffer
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        Ok(WindowSize {
+            width: framebuffer.width,
+            height: framebuffer.height,
+        })
+    }
+
+    /// Get the size of a window's client area in logical pixels
+    #[napi]
+    pub fn get_inner_size(&self, window_id: JsNumber) -> Result<WindowSize> {
+        self.read_window(window_id, |window_state| {
+            let native = &window_state.native;
+            native.logical_size(native.inner_size)
+        })
+    }
+
+    /// Get the size of a window including its decorations, in logical pixels
+    #[napi]
+    pub fn get_outer_size(&self, window_id: JsNumber) -> Result<WindowSize> {
+        self.read_window(window_id, |window_state| {
+            let native = &window_state.native;
+            native.logical_size(native.outer_size)
+        })
+    }
+
+    #[napi]
+    pub fn is_focused(&self, window_id: JsNumber) -> Result<bool> {
+        self.read_window(window_id, |window_state| window_state.native.focused)
+    }
+
+    #[napi]
+    pub fn is_visible(&self, window_id: JsNumber) -> Result<bool> {
+        self.read_window(window_id, |window_state| window_state.native.visible)
+    }
+
+    #[napi]
+    pub fn is_minimized(&self, window_id: JsNumber) -> Result<bool> {
+        self.read_window(window_id, |window_state| window_state.native.minimized)
+    }
+
+    #[napi]
+    pub fn is_maximized(&self, window_id: JsNumber) -> Result<bool> {
+        self.read_window(window_id, |window_state| window_state.native.maximized)
+    }
+
+    /// Whether transparent pixels show what is behind the window: it was created transparent,
+    /// got a 32-bit ARGB visual, and a compositing manager was running at the time.
+    /// `false` until the event loop has created the window
+    #[napi]
+    pub fn supports_transparency(&self, window_id: JsNumber) -> Result<bool> {
+        self.read_window(window_id, |window_state| window_state.supports_transparency)
+    }
+
+    /// Id of the window this one was created as a child of, if any
+    #[napi]
+    pub fn get_parent(&self, window_id: JsNumber) -> Result<Option<u32>> {
+        self.read_window(window_id, |window_state| {
+            window_state.parent.map(|parent| parent as u32)
+        })
+    }
+
+    /// Get the ratio of physical to logical pixels for the monitor the window is on
+    #[napi]
+    pub fn get_scale_factor(&self, window_id: JsNumber) -> Result<f64> {
+        self.read_window(window_id, |window_state| window_state.native.scale_factor)
+    }
+
     /// Check if a window exists
     #[napi]
     pub fn window_exists(&self, window_id: JsNumber) -> Result<bool> {
-        let window_id = js_number_to_u64(window_id)?;
+        let window_id = parse_window_id(window_id)?;
         let state = self
             .state
             .lock()
@@ -1,29 +1,44 @@
+use crate::renderer::window_manager::blend;
+use crate::renderer::window_manager::command_buffer::{DrawContext, DrawOp};
+use crate::renderer::window_manager::command_queue::CommandReceiver;
 use crate::renderer::window_manager::types::*;
+use crate::renderer::window_manager::x11_utils;
+use napi::bindgen_prelude::FnArgs;
+use napi::threadsafe_function::ThreadsafeFunctionCallMode;
 use std::collections::HashMap;
-use std::sync::Arc;
-use winit::dpi::{LogicalPosition, LogicalSize};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoopWindowTarget};
-use winit::window::WindowId;
+use winit::window::{CursorGrabMode, CursorIcon, Fullscreen, WindowId};
 
 /// The application that runs in the event loop
 pub struct WindowManagerApp {
     pub state: SharedState,
     pub windows: HashMap<WindowId, ManagedWindow>,
+    pub commands: CommandReceiver,
+    /// Origin for animation frame timestamps
+    pub start_time: Instant,
 }
 
+/// Refresh rate assumed when the monitor does not report one
+const DEFAULT_REFRESH_MILLIHERTZ: u32 = 60_000;
+
 impl WindowManagerApp {
-    pub fn new(state: SharedState) -> Self {
+    pub fn new(state: SharedState, commands: CommandReceiver) -> Self {
         Self {
             state,
             windows: HashMap::new(),
+            commands,
+            start_time: Instant::now(),
         }
     }
 
     pub fn handle_event(
         &mut self,
-        event: Event<()>,
-        event_loop: &EventLoopWindowTarget<()>,
+        event: Event<UserEvent>,
+        event_loop: &EventLoopWindowTarget<UserEvent>,
         control_flow: &mut ControlFlow,
     ) {
         *control_flow = ControlFlow::Wait;
@@ -52,12 +67,53 @@ impl WindowManagerApp {
                         window_state.needs_redraw = true;
                     }
                 }
+                self.refresh_native_state(window_id);
+                self.apply_resize(window_id);
+            }
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::Focused(focused),
+            } => {
+                self.refresh_native_state(window_id);
+                if focused {
+                    self.focus_modal_child(window_id);
+                }
+            }
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::Moved(_) | WindowEvent::Occluded(_),
+            } => {
+                self.refresh_native_state(window_id);
+            }
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::ScaleFactorChanged { .. },
+            } => {
+                self.refresh_native_state(window_id);
+                self.apply_scale_factor(window_id);
+                // Usually a monitor was added, removed or reconfigured
+                self.refresh_monitors(event_loop);
+            }
+            Event::Resumed => {
+                self.refresh_monitors(event_loop);
+            }
+            Event::UserEvent(UserEvent::Wake) => {
+                // JS queued work: apply it now instead of waiting for the next OS event
+                self.process_commands(event_loop);
             }
             Event::AboutToWait => {
                 // Process commands when the event loop is about to wait
                 // This ensures commands are processed even without window events
                 self.process_commands(event_loop);
 
+                // Fire animation frame callbacks and step fades, then sleep until the next
+                // one is due
+                let next_frame = self.run_animation_frames(event_loop);
+                let next_fade = self.run_fades(event_loop);
+                if let Some(wake_at) = next_frame.into_iter().chain(next_fade).min() {
+                    *control_flow = ControlFlow::WaitUntil(wake_at);
+                }
+
                 // Check if we should exit
                 let should_exit = {
                     let state = self.state.lock().expect("Lock poisoned");
@@ -72,13 +128,8 @@ impl WindowManagerApp {
         }
     }
 
-    pub fn process_commands(&mut self, event_loop: &EventLoopWindowTarget<()>) {
-        let commands = {
-            let mut state = self.state.lock().expect("Lock poisoned");
-            std::mem::take(&mut state.pending_commands)
-        };
-
-        for cmd in commands {
+    pub fn process_commands(&mut self, event_loop: &EventLoopWindowTarget<UserEvent>) {
+        for cmd in self.commands.drain() {
             match cmd {
                 WindowCommand::CreateWindow {
                     id,
@@ -90,6 +141,7 @@ impl WindowManagerApp {
                     always_on_top,
                     transparent,
                     decorations,
+                    hints,
                 } => {
                     self.create_window(
                         event_loop,
@@ -102,6 +154,7 @@ impl WindowManagerApp {
                         always_on_top,
                         transparent,
                         decorations,
+                        hints,
                     );
                 }
                 WindowCommand::Present { window_id } => {
@@ -120,7 +173,180 @@ impl WindowManagerApp {
                     self.set_window_title(window_id, title);
                 }
                 WindowCommand::SetIgnoreInput { window_id, ignore } => {
+                    {
+                        let mut state = self.state.lock().expect("Lock poisoned");
+                        if let Some(window_state) = state.windows.get_mut(&window_id) {
+                            window_state.ignore_input = ignore;
+                        }
+                    }
                     self.set_window_ignore_input(window_id, ignore);
+                    // Winit's hit test shares the X input shape with modal blocking and
+                    // alpha hit testing, so apply the combined region over it
+                    self.update_input_region(window_id);
+                }
+                WindowCommand::SetCursorIcon { window_id, cursor } => {
+                    self.set_window_cursor_icon(window_id, cursor);
+                }
+                WindowCommand::SetCursorVisible { window_id, visible } => {
+                    self.set_window_cursor_visible(window_id, visible);
+                }
+                WindowCommand::SetCursorGrab { window_id, mode } => {
+                    self.set_window_cursor_grab(window_id, mode);
+                }
+                WindowCommand::SetCustomCursor {
+                    window_id,
+                    rgba,
+                    width,
+                    height,
+                    hotspot_x,
+                    hotspot_y,
+                } => {
+                    self.set_window_custom_cursor(
+                        window_id, &rgba, width, height, hotspot_x, hotspot_y,
+                    );
+                }
+                WindowCommand::SetShape { window_id, rects } => {
+                    self.set_window_shape(window_id, rects);
+                }
+                WindowCommand::SetAlphaHitTest { window_id, .. } => {
+                    // The threshold is already in the shared state
+                    self.update_input_region(window_id);
+                }
+                WindowCommand::Submit { window_id, ops } => {
+                    self.apply_batch(window_id, ops);
+                }
+                WindowCommand::SetMinimized {
+                    window_id,
+                    minimized,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_minimized(minimized);
+                        // winit reports no event for an unmap or iconify on X11
+                        self.refresh_native_state(managed.window.id());
+                    }
+                }
+                WindowCommand::SetMaximized {
+                    window_id,
+                    maximized,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_maximized(maximized);
+                        self.refresh_native_state(managed.window.id());
+                    }
+                }
+                WindowCommand::SetFullscreen {
+                    window_id,
+                    mode,
+                    monitor,
+                } => {
+                    self.set_window_fullscreen(event_loop, window_id, mode, monitor);
+                    if let Some(managed) = self.managed_window(window_id) {
+                        self.refresh_native_state(managed.window.id());
+                    }
+                }
+                WindowCommand::SetVisible { window_id, visible } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_visible(visible);
+                        self.refresh_native_state(managed.window.id());
+                    }
+                }
+                WindowCommand::Focus { window_id } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.focus_window();
+                    }
+                }
+                WindowCommand::RequestUserAttention {
+                    window_id,
+                    attention,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.request_user_attention(attention);
+                    }
+                }
+                WindowCommand::SetResizable {
+                    window_id,
+                    resizable,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_resizable(resizable);
+                    }
+                }
+                WindowCommand::SetDecorations {
+                    window_id,
+                    decorations,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_decorations(decorations);
+                    }
+                }
+                WindowCommand::PlaceOnMonitor {
+                    window_id,
+                    monitor,
+                    anchor,
+                    offset_x,
+                    offset_y,
+                } => {
+                    self.place_on_monitor(
+                        event_loop, window_id, monitor, anchor, offset_x, offset_y,
+                    );
+                }
+                WindowCommand::SetOpacity { window_id, opacity } => {
+                    if let Some(managed) = self.managed_window_mut(window_id) {
+                        managed.fade = None;
+                        x11_utils::set_window_opacity(&managed.window, opacity);
+                    }
+                }
+                WindowCommand::SetSkipTaskbar { window_id, skip } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        x11_utils::set_net_wm_state(
+                            &managed.window,
+                            c"_NET_WM_STATE_SKIP_TASKBAR",
+                            skip,
+                        );
+                    }
+                }
+                WindowCommand::SetSkipPager { window_id, skip } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        x11_utils::set_net_wm_state(
+                            &managed.window,
+                            c"_NET_WM_STATE_SKIP_PAGER",
+                            skip,
+                        );
+                    }
+                }
+                WindowCommand::SetIcon { window_id, icon } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        managed.window.set_window_icon(Some(icon));
+                    }
+                }
+                WindowCommand::SetSizeConstraints {
+                    window_id,
+                    constraints,
+                } => {
+                    if let Some(managed) = self.managed_window(window_id) {
+                        apply_size_constraints(&managed.window, constraints);
+                    }
+                }
+                WindowCommand::FadeOpacity {
+                    window_id,
+                    to,
+                    duration,
+                    close_when_done,
+                } => {
+                    let from = {
+                        let state = self.state.lock().expect("Lock poisoned");
+                        state.windows.get(&window_id).map(|ws| ws.opacity)
+                    };
+                    if let (Some(from), Some(managed)) = (from, self.managed_window_mut(window_id))
+                    {
+                        managed.fade = Some(OpacityFade {
+                            from,
+                            to,
+                            started_at: Instant::now(),
+                            duration,
+                            close_when_done,
+                        });
+                    }
                 }
                 WindowCommand::CloseWindow { window_id } => {
                     self.close_window_by_id(window_id, event_loop);
@@ -145,10 +371,159 @@ impl WindowManagerApp {
         }
     }
 
+    /// Call the animation frame callbacks of every window whose next frame is due.
+    ///
+    /// Callbacks run on the JS thread; we wait (up to one frame) for them to finish and then
+    /// process the commands they queued, so a frame's drawing and `present` land together.
+    /// Returns when the next pending frame is due, if any.
+    fn run_animation_frames(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<UserEvent>,
+    ) -> Option<Instant> {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        let mut frame_budget = Duration::from_millis(1000);
+
+        {
+            let mut state = self.state.lock().expect("Lock poisoned");
+            if state.frame_requests.is_empty() {
+                return None;
+            }
+
+            let requests = std::mem::take(&mut state.frame_requests);
+            for request in requests {
+                if !state.windows.contains_key(&request.window_id) {
+                    // Window was closed; drop its callbacks
+                    continue;
+                }
+
+                let managed = self
+                    .windows
+                    .values_mut()
+                    .find(|managed| managed.state_id == request.window_id);
+                let Some(managed) = managed else {
+                    // Window not created yet; keep the request for a later frame
+                    state.frame_requests.push(request);
+                    continue;
+                };
+
+                let interval = frame_interval(&managed.window);
+                frame_budget = frame_budget.min(interval);
+                match managed.last_frame_at {
+                    Some(last) if last + interval > now => state.frame_requests.push(request),
+                    _ => due.push(request),
+                }
+            }
+        }
+
+        if !due.is_empty() {
+            let timestamp = now.duration_since(self.start_time).as_secs_f64() * 1000.0;
+            let (done_tx, done_rx) = mpsc::channel();
+            let mut deltas: HashMap<u64, Duration> = HashMap::new();
+            let mut calls = 0;
+
+            for request in due {
+                // Several callbacks for the same window share one frame and one delta
+                let delta = *deltas.entry(request.window_id).or_insert_with(|| {
+                    let managed = self
+                        .windows
+                        .values_mut()
+                        .find(|managed| managed.state_id == request.window_id)
+                        .expect("Window checked above");
+                    let delta = managed
+                        .last_frame_at
+                        .map_or(Duration::ZERO, |last| now.duration_since(last));
+                    managed.last_frame_at = Some(now);
+                    delta
+                });
+
+                let done_tx = done_tx.clone();
+                let status = request.callback.call_with_return_value(
+                    FnArgs::from((timestamp, delta.as_secs_f64() * 1000.0)),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                    move |_, _| {
+                        let _ = done_tx.send(());
+                        Ok(())
+                    },
+                );
+                if status == napi::Status::Ok {
+                    calls += 1;
+                }
+            }
+
+            let deadline = now + frame_budget;
+            for _ in 0..calls {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if done_rx.recv_timeout(remaining).is_err() {
+                    break;
+                }
+            }
+
+            // Apply what the callbacks drew in this same frame
+            self.process_commands(event_loop);
+        }
+
+        let state = self.state.lock().expect("Lock poisoned");
+        state
+            .frame_requests
+            .iter()
+            .filter_map(|request| {
+                self.windows
+                    .values()
+                    .find(|managed| managed.state_id == request.window_id)
+                    .map(|managed| match managed.last_frame_at {
+                        Some(last) => last + frame_interval(&managed.window),
+                        None => now,
+                    })
+            })
+            .min()
+    }
+
+    /// Step running opacity fades, closing windows whose fade asked for it.
+    /// Returns when the next step is due, if any fade is still running.
+    fn run_fades(&mut self, event_loop: &EventLoopWindowTarget<UserEvent>) -> Option<Instant> {
+        let now = Instant::now();
+        let mut next_step: Option<Instant> = None;
+        let mut to_close = Vec::new();
+
+        for managed in self.windows.values_mut() {
+            let Some(fade) = managed.fade else {
+                continue;
+            };
+
+            let opacity = fade.opacity_at(now);
+            x11_utils::set_window_opacity(&managed.window, opacity);
+            if let Some(window_state) = self
+                .state
+                .lock()
+                .expect("Lock poisoned")
+                .windows
+                .get_mut(&managed.state_id)
+            {
+                window_state.opacity = opacity;
+            }
+
+            if fade.is_done(now) {
+                managed.fade = None;
+                if fade.close_when_done {
+                    to_close.push(managed.state_id);
+                }
+            } else {
+                let step = now + frame_interval(&managed.window);
+                next_step = Some(next_step.map_or(step, |next| next.min(step)));
+            }
+        }
+
+        for window_id in to_close {
+            self.close_window_by_id(window_id, event_loop);
+        }
+        next_step
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn create_window(
         &mut self,
-        event_loop: &EventLoopWindowTarget<()>,
+        event_loop: &EventLoopWindowTarget<UserEvent>,
         id: u64,
         width: u32,
         height: u32,
@@ -158,15 +533,48 @@ impl WindowManagerApp {
         always_on_top: bool,
         transparent: bool,
         decorations: bool,
+        hints: CreateHints,
     ) {
         use winit::window::WindowBuilder;
 
+        // Created hidden so window manager hints are in place before it is first mapped
         let window_builder = WindowBuilder::new()
             .with_title(&title)
             .with_inner_size(LogicalSize::new(width, height))
             .with_transparent(transparent)
             .with_decorations(decorations)
-            .with_visible(true);
+            .with_visible(false);
+
+        let constraints = hints.size_constraints;
+        let mut window_builder = window_builder;
+        if let Some((min_width, min_height)) = constraints.min_size {
+            window_builder =
+                window_builder.with_min_inner_size(LogicalSize::new(min_width, min_height));
+        }
+        if let Some((max_width, max_height)) = constraints.max_size {
+            window_builder =
+                window_builder.with_max_inner_size(LogicalSize::new(max_width, max_height));
+        }
+        if let Some((width_inc, height_inc)) = constraints.resize_increments {
+            window_builder =
+                window_builder.with_resize_increments(LogicalSize::new(width_inc, height_inc));
+        }
+
+        #[cfg(target_os = "linux")]
+        let window_builder = {
+            use winit::platform::x11::WindowBuilderExtX11;
+            let window_builder =
+                window_builder.with_x11_window_type(vec![x11_window_type(hints.window_type)]);
+            let window_builder = match hints.wm_class.clone() {
+                Some((instance, class)) => window_builder.with_name(class, instance),
+                None => window_builder,
+            };
+            // Ask for an ARGB visual explicitly rather than relying on the default one
+            match transparent.then(x11_utils::find_argb_visual).flatten() {
+                Some(visual_id) => window_builder.with_x11_visual(visual_id),
+                None => window_builder,
+            }
+        };
 
         let window = Arc::new(
             window_builder
@@ -174,6 +582,27 @@ impl WindowManagerApp {
                 .expect("Failed to create window"),
         );
 
+        let mut wm_states = Vec::new();
+        if hints.skip_taskbar {
+            wm_states.push(c"_NET_WM_STATE_SKIP_TASKBAR");
+        }
+        if hints.skip_pager {
+            wm_states.push(c"_NET_WM_STATE_SKIP_PAGER");
+        }
+        if hints.modal {
+            wm_states.push(c"_NET_WM_STATE_MODAL");
+        }
+        if let Some(parent) = hints.parent.and_then(|parent| self.managed_window(parent)) {
+            x11_utils::set_transient_for(&window, &parent.window);
+        }
+        if !wm_states.is_empty() {
+            x11_utils::init_net_wm_state(&window, &wm_states);
+        }
+        if constraints.aspect_ratio.is_some() {
+            x11_utils::set_aspect_ratio(&window, constraints.aspect_ratio);
+        }
+        window.set_visible(true);
+
         // Note: always_on_top is not directly supported in winit 0.29 WindowBuilder
         // It would require platform-specific code or window level adjustments after creation
         let _ = always_on_top; // Silence unused warning
@@ -183,6 +612,16 @@ impl WindowManagerApp {
         }
 
         let winit_id = window.id();
+        let supports_transparency = transparent
+            && x11_utils::window_depth(&window) == Some(32)
+            && x11_utils::compositor_running();
+        if transparent && !supports_transparency {
+            eprintln!(
+                "Window {} was requested transparent, but transparency is not available \
+                 (no ARGB visual or no compositing manager)",
+                id
+            );
+        }
 
         // Create a pixmap for CPU-based rendering
         let pixmap = tiny_skia::Pixmap::new(width, height)
@@ -194,6 +633,8 @@ impl WindowManagerApp {
                 window: window.clone(),
                 pixmap,
                 state_id: id,
+                last_frame_at: None,
+                fade: None,
             },
         );
 
@@ -201,36 +642,50 @@ impl WindowManagerApp {
         let mut state = self.state.lock().expect("Lock poisoned");
 
         if let Some(window_state) = state.windows.get_mut(&id) {
-            // Window was pre-registered, just update the winit_id
+            // Window was pre-registered, just update the winit_id and native state
             window_state.winit_id = Some(winit_id);
+            window_state.supports_transparency = supports_transparency;
+            window_state.native = NativeWindowState::query(&window);
+            // Opacity set before the window existed
+            if window_state.opacity < 1.0 {
+                x11_utils::set_window_opacity(&window, window_state.opacity);
+            }
         } else {
             // Fallback: create window state if not pre-registered (shouldn't happen)
-            let pixel_count = (width * height) as usize;
-            // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
-            let pixel_buffer = if transparent {
-                vec![0x00000000u32; pixel_count] // Fully transparent
-            } else {
-                vec![0xFF000000u32; pixel_count] // Opaque black
-            };
-
             state.windows.insert(
                 id,
                 WindowState {
                     width,
                     height,
-                    pixel_buffer,
+                    framebuffer: Framebuffer::shared(width, height, transparent),
                     needs_redraw: true,
                     title,
                     x,
                     y,
                     always_on_top,
                     transparent,
+                    supports_transparency,
                     decorations,
+                    alpha_hit_threshold: None,
+                    ignore_input: false,
+                    color_format: ColorFormat::default(),
+                    opacity: 1.0,
+                    parent: hints.parent,
+                    blocks_parent_input: hints.block_parent_input,
+                    size_constraints: hints.size_constraints,
+                    stats: FrameStats::default(),
+                    native: NativeWindowState::query(&window),
                     winit_id: Some(winit_id),
                 },
             );
         }
 
+        drop(state);
+        self.apply_scale_factor(winit_id);
+        if let Some(parent) = hints.parent.filter(|_| hints.block_parent_input) {
+            self.update_input_region(parent);
+        }
+
         window.request_redraw();
     }
 
@@ -241,60 +696,313 @@ impl WindowManagerApp {
             None => return,
         };
         
-        // Get the pixel buffer data from shared state
-        let (pixel_buffer, width, height, is_transparent) = {
-            let state = self.state.lock().expect("Lock poisoned");
+        // Only metadata is read under the shared lock; the pixels have their own lock
+        let (framebuffer, is_transparent, alpha_hit_threshold) = {
+            let mut state = self.state.lock().expect("Lock poisoned");
+            // Ignoring input or a blocking modal child keeps the input region empty
+            let alpha_hit_threshold = match state.input_region(managed.state_id) {
+                Some(InputRegion::Alpha(threshold)) => Some(threshold),
+                _ => None,
+            };
 
-            if let Some(window_state) = state.windows.get(&managed.state_id) {
+            if let Some(window_state) = state.windows.get_mut(&managed.state_id) {
+                // Cleared before converting so that writes made during this frame redraw again
+                window_state.needs_redraw = false;
                 (
-                    window_state.pixel_buffer.clone(),
-                    window_state.width,
-                    window_state.height,
+                    window_state.framebuffer.clone(),
                     window_state.transparent,
+                    alpha_hit_threshold,
                 )
             } else {
                 return;
             }
         };
-        
-        // Convert ARGB u32 pixels to RGBA u8 format for tiny-skia
-        // tiny-skia expects [R, G, B, A] per pixel in a Vec<u8>
-        let mut rgba_data = vec![0u8; (width * height * 4) as usize];
-        
-        for i in 0..pixel_buffer.len() {
-            let argb = pixel_buffer[i];
 
-            // Extract ARGB components
-            let alpha = (argb >> 24) & 0xFF;
-            let red = (argb >> 16) & 0xFF;
-            let green = (argb >> 8) & 0xFF;
-            let blue = argb & 0xFF;
+        let convert_start = Instant::now();
+        let input_rects = {
+            let framebuffer = framebuffer.lock().expect("Lock poisoned");
 
-            // Write RGBA to buffer
-            let idx = i * 4;
-            rgba_data[idx] = red as u8;
-            rgba_data[idx + 1] = green as u8;
-            rgba_data[idx + 2] = blue as u8;
-            rgba_data[idx + 3] = alpha as u8;
-        }
+            if managed.pixmap.width() != framebuffer.width
+                || managed.pixmap.height() != framebuffer.height
+            {
+                managed.pixmap = tiny_skia::Pixmap::new(framebuffer.width, framebuffer.height)
+                    .expect("Failed to create pixmap");
+            }
 
-        // Update the pixmap with the new pixel data
-        managed.pixmap.data_mut().copy_from_slice(&rgba_data);
+            // Convert straight-alpha ARGB u32 pixels into the pixmap's RGBA u8 data
+            // tiny-skia expects premultiplied [R, G, B, A] per pixel
+            let rgba_data = managed.pixmap.data_mut();
+            for (rgba, &argb) in rgba_data.chunks_exact_mut(4).zip(&framebuffer.pixels) {
+                rgba.copy_from_slice(&blend::premultiply(argb));
+            }
+
+            // Keep the input region in sync with what is on screen
+            alpha_hit_threshold.map(|threshold| {
+                x11_utils::alpha_mask_rects(
+                    &framebuffer.pixels,
+                    framebuffer.width,
+                    framebuffer.height,
+                    threshold,
+                )
+            })
+        };
+        let convert_time = convert_start.elapsed();
 
-        // Get the pixmap reference for presentation
-        let pixmap_ref = &managed.pixmap;
-        let window_clone = managed.window.clone();
-        
         // Render to the window using X11
-        present_to_window(&window_clone, pixmap_ref, is_transparent);
-        
+        let window_clone = managed.window.clone();
+        let upload_start = Instant::now();
+        let bytes_uploaded = present_to_window(&window_clone, &managed.pixmap, is_transparent);
+        let upload_time = upload_start.elapsed();
+
+        if let Some(rects) = input_rects {
+            x11_utils::set_shape_region(&window_clone, x11_utils::SHAPE_INPUT, Some(&rects));
+        }
+
         // Reset needs_redraw flag after rendering
         let mut state = self.state.lock().expect("Lock poisoned");
         if let Some(window_state) = state.windows.get_mut(&managed.state_id) {
-            window_state.needs_redraw = false;
+            window_state
+                .stats
+                .record_frame(convert_time, upload_time, bytes_uploaded);
         }
     }
 
+    /// Apply a submitted batch: pixel operations are applied in order under one framebuffer
+    /// lock, then window operations run in the order they were submitted
+    fn apply_batch(&self, window_id: u64, ops: Vec<DrawOp>) {
+        let framebuffer = {
+            let state = self.state.lock().expect("Lock poisoned");
+            match state.windows.get(&window_id) {
+                Some(window_state) => window_state.framebuffer.clone(),
+                None => return,
+            }
+        };
+
+        {
+            let mut framebuffer = framebuffer.lock().expect("Lock poisoned");
+            let mut context = DrawContext::new(&framebuffer);
+            for op in &ops {
+                context.apply(&mut framebuffer, op);
+            }
+        }
+
+        let mut present = false;
+        for op in ops {
+            match op {
+                DrawOp::Present => present = true,
+                DrawOp::SetPosition { x, y } => self.set_window_position(window_id, x, y),
+                DrawOp::SetTitle { title } => self.set_window_title(window_id, title),
+                _ => {}
+            }
+        }
+
+        // Marked after the pixels are in place so a concurrent render cannot miss them
+        if present {
+            let mut state = self.state.lock().expect("Lock poisoned");
+            if let Some(window_state) = state.windows.get_mut(&window_id) {
+                window_state.needs_redraw = true;
+            }
+        }
+    }
+
+    /// Re-read the connected monitors into the shared state for `getMonitors`
+    pub fn refresh_monitors(&self, event_loop: &EventLoopWindowTarget<UserEvent>) {
+        let monitors = monitor_infos(event_loop);
+        self.state.lock().expect("Lock poisoned").monitors = monitors;
+    }
+
+    /// Re-read a window's native state after winit reports a change, keeping the
+    /// requested position in sync with where the window actually is
+    fn refresh_native_state(&self, window_id: WindowId) {
+        let Some(managed) = self.windows.get(&window_id) else {
+            return;
+        };
+        let native = NativeWindowState::query(&managed.window);
+
+        let mut state = self.state.lock().expect("Lock poisoned");
+        if let Some(window_state) = state.windows.get_mut(&managed.state_id) {
+            if let Some(position) = native.position() {
+                window_state.x = Some(position.x);
+                window_state.y = Some(position.y);
+            }
+            window_state.native = native;
+        }
+    }
+
+    /// Size a window's framebuffer in physical pixels for the scale factor of the monitor
+    /// it is on, resampling what has been drawn so far
+    fn apply_scale_factor(&self, window_id: WindowId) {
+        let Some(managed) = self.windows.get(&window_id) else {
+            return;
+        };
+        let scale_factor = managed.window.scale_factor();
+
+        let mut state = self.state.lock().expect("Lock poisoned");
+        if let Some(window_state) = state.windows.get_mut(&managed.state_id) {
+            let mut framebuffer = window_state.framebuffer.lock().expect("Lock poisoned");
+            if framebuffer.scale_factor != scale_factor {
+                framebuffer.rescale(window_state.width, window_state.height, scale_factor);
+                drop(framebuffer);
+                window_state.needs_redraw = true;
+            }
+        }
+    }
+
+    /// Reallocate a window's framebuffer for its new client area, clamped to the window's
+    /// size limits in case the window manager ignored them
+    fn apply_resize(&self, window_id: WindowId) {
+        let Some(managed) = self.windows.get(&window_id) else {
+            return;
+        };
+
+        let mut state = self.state.lock().expect("Lock poisoned");
+        let Some(window_state) = state.windows.get_mut(&managed.state_id) else {
+            return;
+        };
+        let native = window_state.native;
+        if native.minimized || native.inner_size.width == 0 || native.inner_size.height == 0 {
+            return;
+        }
+
+        let size = native.logical_size(native.inner_size);
+        let (width, height) = window_state.size_constraints.clamp(size.width, size.height);
+        if (width, height) == (window_state.width, window_state.height) {
+            return;
+        }
+
+        let background = if window_state.transparent {
+            0x00000000
+        } else {
+            0xFF000000
+        };
+        window_state
+            .framebuffer
+            .lock()
+            .expect("Lock poisoned")
+            .resize(width, height, background);
+        window_state.width = width;
+        window_state.height = height;
+        window_state.needs_redraw = true;
+    }
+
+    /// Find the native window for a window id
+    fn managed_window(&self, window_id: u64) -> Option<&ManagedWindow> {
+        self.windows
+            .values()
+            .find(|managed| managed.state_id == window_id)
+    }
+
+    fn managed_window_mut(&mut self, window_id: u64) -> Option<&mut ManagedWindow> {
+        self.windows
+            .values_mut()
+            .find(|managed| managed.state_id == window_id)
+    }
+
+    fn set_window_fullscreen(
+        &self,
+        event_loop: &EventLoopWindowTarget<UserEvent>,
+        window_id: u64,
+        mode: Option<FullscreenMode>,
+        monitor: Option<u32>,
+    ) {
+        let Some(managed) = self.managed_window(window_id) else {
+            return;
+        };
+
+        let Some(mode) = mode else {
+            managed.window.set_fullscreen(None);
+            return;
+        };
+
+        let monitor = match monitor {
+            Some(index) => match event_loop.available_monitors().nth(index as usize) {
+                Some(monitor) => Some(monitor),
+                None => {
+                    eprintln!(
+                        "Cannot make window {} fullscreen: there is no monitor {}",
+                        window_id, index
+                    );
+                    return;
+                }
+            },
+            None => managed.window.current_monitor(),
+        };
+
+        let fullscreen = match mode {
+            FullscreenMode::Borderless => Fullscreen::Borderless(monitor),
+            FullscreenMode::Exclusive => {
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|video_mode| {
+                        let size = video_mode.size();
+                        (
+                            size.width as u64 * size.height as u64,
+                            video_mode.refresh_rate_millihertz(),
+                        )
+                    })
+                });
+                match video_mode {
+                    Some(video_mode) => Fullscreen::Exclusive(video_mode),
+                    None => {
+                        eprintln!(
+                            "No video modes for window {}'s monitor, using borderless fullscreen",
+                            window_id
+                        );
+                        Fullscreen::Borderless(monitor)
+                    }
+                }
+            }
+        };
+        managed.window.set_fullscreen(Some(fullscreen));
+    }
+
+    fn place_on_monitor(
+        &self,
+        event_loop: &EventLoopWindowTarget<UserEvent>,
+        window_id: u64,
+        monitor: Option<u32>,
+        anchor: MonitorAnchor,
+        offset_x: i32,
+        offset_y: i32,
+    ) {
+        let Some(managed) = self.managed_window(window_id) else {
+            return;
+        };
+
+        let monitors = monitor_infos(event_loop);
+        let target = match monitor {
+            Some(index) => monitors.get(index as usize),
+            None => managed.window.current_monitor().and_then(|current| {
+                let position = current.position();
+                monitors
+                    .iter()
+                    .find(|info| info.x == position.x && info.y == position.y)
+            }),
+        };
+        let Some(target) = target else {
+            eprintln!(
+                "Cannot place window {}: monitor {:?} not found",
+                window_id, monitor
+            );
+            return;
+        };
+
+        let area = target.work_area;
+        let size = managed.window.outer_size();
+        let (align_x, align_y) = anchor.alignment();
+        // Offsets point inwards, so they are mirrored on the right and bottom edges
+        let offset = |offset: i32, align: f64| {
+            (offset as f64 * target.scale_factor * (1.0 - 2.0 * align)).round() as i32
+        };
+        let free_x = area.width as f64 - size.width as f64;
+        let free_y = area.height as f64 - size.height as f64;
+        let x = area.x + (free_x * align_x).round() as i32 + offset(offset_x, align_x);
+        let y = area.y + (free_y * align_y).round() as i32 + offset(offset_y, align_y);
+
+        managed
+            .window
+            .set_outer_position(PhysicalPosition::new(x, y));
+    }
+
     fn request_redraw(&self, window_id: u64) {
         for managed in self.windows.values() {
             if managed.state_id == window_id {
@@ -346,38 +1054,157 @@ impl WindowManagerApp {
         }
     }
 
-    fn close_window_by_id(&mut self, window_id: u64, _event_loop: &EventLoopWindowTarget<()>) {
-        let mut to_remove = None;
-        for (id, managed) in &self.windows {
+    fn set_window_shape(&self, window_id: u64, rects: Option<Vec<ShapeRect>>) {
+        for managed in self.windows.values() {
             if managed.state_id == window_id {
-                to_remove = Some(*id);
+                x11_utils::set_shape_region(
+                    &managed.window,
+                    x11_utils::SHAPE_BOUNDING,
+                    rects.as_deref(),
+                );
                 break;
             }
         }
+    }
 
-        if let Some(id) = to_remove {
-            self.windows.remove(&id);
-            let mut state = self.state.lock().expect("Lock poisoned");
-            state.windows.remove(&window_id);
+    fn set_window_cursor_icon(&self, window_id: u64, cursor: CursorIcon) {
+        for managed in self.windows.values() {
+            if managed.state_id == window_id {
+                managed.window.set_cursor_icon(cursor);
+                break;
+            }
         }
     }
 
-    fn close_window(&mut self, window_id: WindowId, event_loop: &EventLoopWindowTarget<()>) {
-        self.windows.remove(&window_id);
-        let mut state = self.state.lock().expect("Lock poisoned");
+    fn set_window_cursor_visible(&self, window_id: u64, visible: bool) {
+        for managed in self.windows.values() {
+            if managed.state_id == window_id {
+                managed.window.set_cursor_visible(visible);
+                break;
+            }
+        }
+    }
 
-        // Find and remove by winit_id
-        let mut id_to_remove = None;
-        for (id, ws) in &state.windows {
-            if ws.winit_id == Some(window_id) {
-                id_to_remove = Some(*id);
+    fn set_window_cursor_grab(&self, window_id: u64, mode: CursorGrabMode) {
+        for managed in self.windows.values() {
+            if managed.state_id == window_id {
+                if let Err(e) = managed.window.set_cursor_grab(mode) {
+                    eprintln!("Failed to set cursor grab for window {}: {}", window_id, e);
+                }
                 break;
             }
         }
-        if let Some(id) = id_to_remove {
-            state.windows.remove(&id);
+    }
+
+    fn set_window_custom_cursor(
+        &self,
+        window_id: u64,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    ) {
+        for managed in self.windows.values() {
+            if managed.state_id == window_id {
+                x11_utils::set_custom_cursor(
+                    &managed.window,
+                    rgba,
+                    width,
+                    height,
+                    hotspot_x,
+                    hotspot_y,
+                );
+                break;
+            }
+        }
+    }
+
+    /// While a parent has a modal child that blocks its input, move focus to the child
+    fn focus_modal_child(&self, window_id: WindowId) {
+        let Some(parent_id) = self.windows.get(&window_id).map(|managed| managed.state_id) else {
+            return;
+        };
+        let modal_child = {
+            let state = self.state.lock().expect("Lock poisoned");
+            state.blocking_child(parent_id)
+        };
+
+        if let Some(child) = modal_child.and_then(|child| self.managed_window(child)) {
+            child.window.focus_window();
+        }
+    }
+
+    /// Close a window and, recursively, the windows created as its children
+    fn close_window_by_id(
+        &mut self,
+        window_id: u64,
+        _event_loop: &EventLoopWindowTarget<UserEvent>,
+    ) {
+        let mut state = self.state.lock().expect("Lock poisoned");
+
+        // Parents whose input a closing modal child blocks
+        let mut unblocked = Vec::new();
+        let mut to_close = vec![window_id];
+        let mut index = 0;
+        while let Some(&parent) = to_close.get(index) {
+            to_close.extend(
+                state
+                    .windows
+                    .iter()
+                    .filter(|(_, ws)| ws.parent == Some(parent))
+                    .map(|(id, _)| *id),
+            );
+            index += 1;
+        }
+
+        for id in to_close {
+            if let Some(ws) = state.windows.remove(&id) {
+                unblocked.extend(ws.parent.filter(|_| ws.blocks_parent_input));
+            }
+            self.windows.retain(|_, managed| managed.state_id != id);
+        }
+
+        drop(state);
+        for parent in unblocked {
+            self.update_input_region(parent);
+        }
+    }
+
+    /// Apply a window's input region: empty while it ignores input or a modal child blocks
+    /// it, otherwise the alpha hit test region (derived on the next present) or the whole
+    /// window
+    fn update_input_region(&self, window_id: u64) {
+        let Some(managed) = self.managed_window(window_id) else {
+            return;
+        };
+        let region = {
+            let state = self.state.lock().expect("Lock poisoned");
+            state.input_region(window_id)
+        };
+
+        match region {
+            Some(InputRegion::Empty) => {
+                x11_utils::set_shape_region(&managed.window, x11_utils::SHAPE_INPUT, Some(&[]));
+            }
+            Some(InputRegion::Alpha(_)) => managed.window.request_redraw(),
+            Some(InputRegion::Whole) => {
+                x11_utils::set_shape_region(&managed.window, x11_utils::SHAPE_INPUT, None);
+            }
+            None => {}
+        }
+    }
+
+    fn close_window(&mut self, window_id: WindowId, event_loop: &EventLoopWindowTarget<UserEvent>) {
+        let state_id = self.windows.get(&window_id).map(|managed| managed.state_id);
+        match state_id {
+            Some(state_id) => self.close_window_by_id(state_id, event_loop),
+            None => {
+                self.windows.remove(&window_id);
+            }
         }
 
+        let mut state = self.state.lock().expect("Lock poisoned");
         if state.windows.is_empty() {
             state.should_exit = true;
             event_loop.exit();
@@ -385,7 +1212,50 @@ impl WindowManagerApp {
     }
 }
 
-fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pixmap, _is_transparent: bool) {
+/// Apply all of a window's size limits to the native window
+fn apply_size_constraints(window: &Arc<winit::window::Window>, constraints: SizeConstraints) {
+    let logical = |(width, height): (u32, u32)| LogicalSize::new(width, height);
+    window.set_min_inner_size(constraints.min_size.map(logical));
+    window.set_max_inner_size(constraints.max_size.map(logical));
+    window.set_resize_increments(constraints.resize_increments.map(logical));
+    x11_utils::set_aspect_ratio(window, constraints.aspect_ratio);
+}
+
+/// The winit X11 window type for a `windowType` hint
+#[cfg(target_os = "linux")]
+fn x11_window_type(window_type: WindowType) -> winit::platform::x11::XWindowType {
+    use winit::platform::x11::XWindowType;
+
+    match window_type {
+        WindowType::Normal => XWindowType::Normal,
+        WindowType::Dialog => XWindowType::Dialog,
+        WindowType::Utility => XWindowType::Utility,
+        WindowType::Toolbar => XWindowType::Toolbar,
+        WindowType::Dock => XWindowType::Dock,
+        WindowType::Splash => XWindowType::Splash,
+        WindowType::Notification => XWindowType::Notification,
+        WindowType::Tooltip => XWindowType::Tooltip,
+        WindowType::PopupMenu => XWindowType::PopupMenu,
+        WindowType::Desktop => XWindowType::Desktop,
+    }
+}
+
+/// Time between frames on the monitor currently showing the window
+fn frame_interval(window: &winit::window::Window) -> Duration {
+    let millihertz = window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+        .filter(|&mhz| mhz > 0)
+        .unwrap_or(DEFAULT_REFRESH_MILLIHERTZ);
+    Duration::from_secs_f64(1000.0 / millihertz as f64)
+}
+
+/// Upload a pixmap to the window, returning the number of bytes sent to the X server
+fn present_to_window(
+    window: &Arc<winit::window::Window>,
+    pixmap: &tiny_skia::Pixmap,
+    _is_transparent: bool,
+) -> usize {
     #[cfg(target_os = "linux")]
     {
         use std::ffi::c_void;
@@ -403,7 +1273,7 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
                 let display = XOpenDisplay(std::ptr::null());
                 if display.is_null() {
                     eprintln!("Failed to open X11 display");
-                    return;
+                    return 0;
                 }
 
                 let x_window = xlib_handle.window as xlib::Window;
@@ -413,9 +1283,9 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
                 if xlib::XGetWindowAttributes(display, x_window, &mut window_attrs) == 0 {
                     eprintln!("Failed to get window attributes");
                     xlib::XCloseDisplay(display);
-                    return;
+                    return 0;
                 }
-                
+
                 let visual = window_attrs.visual;
                 let depth = window_attrs.depth as c_uint;
 
@@ -423,43 +1293,20 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
                 let width = pixmap.width() as c_int;
                 let height = pixmap.height() as c_int;
 
-                // Get the pixmap data (RGBA format from tiny-skia)
-                let rgba_data = pixmap.data();
-                
-                // Convert RGBA to the format expected by X11 based on depth
-                let mut x11_data: Vec<u8>;
-                
-                if depth == 32 {
-                    // 32-bit depth: use BGRA format (little-endian ARGB)
-                    x11_data = Vec::with_capacity(rgba_data.len());
-                    for chunk in rgba_data.chunks_exact(4) {
-                        let r = chunk[0];
-                        let g = chunk[1];
-                        let b = chunk[2];
-                        let a = chunk[3];
-                        x11_data.push(b);
-                        x11_data.push(g);
-                        x11_data.push(r);
-                        x11_data.push(a);
-                    }
-                } else {
-                    // 24-bit depth: use BGR format (3 bytes per pixel)
-                    let pixel_count = (width * height) as usize;
-                    x11_data = Vec::with_capacity(pixel_count * 3);
-                    for chunk in rgba_data.chunks_exact(4) {
-                        let r = chunk[0];
-                        let g = chunk[1];
-                        let b = chunk[2];
-                        // Ignore alpha for 24-bit depth
-                        x11_data.push(b);
-                        x11_data.push(g);
-                        x11_data.push(r);
-                    }
-                }
-                
+                // Convert the pixmap's premultiplied RGBA to the layout of the visual
+                let converted =
+                    x11_utils::VisualFormat::query(display, visual, depth).and_then(|format| {
+                        x11_utils::pixmap_to_visual(pixmap.data(), width as u32, &format)
+                            .map(|(data, bytes_per_line)| (data, bytes_per_line, format))
+                    });
+                let Some((x11_data, bytes_per_line, format)) = converted else {
+                    eprintln!("Unsupported X11 visual (depth {})", depth);
+                    xlib::XCloseDisplay(display);
+                    return 0;
+                };
+
                 let data = x11_data.as_ptr() as *mut c_void;
-                let bytes_per_line = if depth == 32 { 0 } else { width * 3 };
-                let bitmap_pad = if depth == 32 { 32 } else { 24 };
+                let bitmap_pad = format.scanline_pad;
                 
                 let ximage = xlib::XCreateImage(
                     display,
@@ -477,7 +1324,7 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
                 if ximage.is_null() {
                     eprintln!("Failed to create XImage");
                     xlib::XCloseDisplay(display);
-                    return;
+                    return 0;
                 }
 
                 // Create a graphics context for the window
@@ -508,6 +1355,8 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
                 (*ximage).data = std::ptr::null_mut();
                 xlib::XDestroyImage(ximage);
                 xlib::XCloseDisplay(display);
+
+                return x11_data.len();
             }
         }
     }
@@ -520,4 +1369,57 @@ fn present_to_window(window: &Arc<winit::window::Window>, pixmap: &tiny_skia::Pi
         let _ = is_transparent;
         // TODO: Implement for Windows and macOS
     }
+
+    0
+}
+
+/// Describe the available monitors, clipping the desktop work area to each of them
+fn monitor_infos(event_loop: &EventLoopWindowTarget<UserEvent>) -> Vec<MonitorInfo> {
+    let primary = event_loop.primary_monitor();
+    let work_area = x11_utils::work_area();
+
+    event_loop
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let position = monitor.position();
+            let size = monitor.size();
+            let bounds = MonitorRect {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            };
+
+            let work_area = work_area
+                .and_then(|(x, y, width, height)| {
+                    let x0 = x.max(bounds.x);
+                    let y0 = y.max(bounds.y);
+                    let x1 = (x + width as i32).min(bounds.x + bounds.width as i32);
+                    let y1 = (y + height as i32).min(bounds.y + bounds.height as i32);
+                    (x1 > x0 && y1 > y0).then(|| MonitorRect {
+                        x: x0,
+                        y: y0,
+                        width: (x1 - x0) as u32,
+                        height: (y1 - y0) as u32,
+                    })
+                })
+                .unwrap_or(bounds);
+
+            MonitorInfo {
+                index: index as u32,
+                name: monitor.name(),
+                x: bounds.x,
+                y: bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+                scale_factor: monitor.scale_factor(),
+                refresh_rate: monitor
+                    .refresh_rate_millihertz()
+                    .map(|millihertz| millihertz as f64 / 1000.0),
+                primary: primary.as_ref() == Some(&monitor),
+                work_area,
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,390 @@
+//! Compositing of straight-alpha ARGB pixels with the tiny-skia blend modes
+//!
+//! Framebuffers hold straight (non-premultiplied) ARGB `u32` pixels. Blending converts both
+//! pixels to premultiplied floats, applies the same formulas Skia uses for each
+//! `BlendMode`, and converts the result back to straight ARGB.
+
+use tiny_skia::BlendMode;
+
+/// Blend mode names accepted from JS, in the order used by the binary `setBlendMode` opcode
+pub const BLEND_MODES: [(&str, BlendMode); 29] = [
+    ("clear", BlendMode::Clear),
+    ("source", BlendMode::Source),
+    ("destination", BlendMode::Destination),
+    ("source-over", BlendMode::SourceOver),
+    ("destination-over", BlendMode::DestinationOver),
+    ("source-in", BlendMode::SourceIn),
+    ("destination-in", BlendMode::DestinationIn),
+    ("source-out", BlendMode::SourceOut),
+    ("destination-out", BlendMode::DestinationOut),
+    ("source-atop", BlendMode::SourceAtop),
+    ("destination-atop", BlendMode::DestinationAtop),
+    ("xor", BlendMode::Xor),
+    ("plus", BlendMode::Plus),
+    ("modulate", BlendMode::Modulate),
+    ("screen", BlendMode::Screen),
+    ("overlay", BlendMode::Overlay),
+    ("darken", BlendMode::Darken),
+    ("lighten", BlendMode::Lighten),
+    ("color-dodge", BlendMode::ColorDodge),
+    ("color-burn", BlendMode::ColorBurn),
+    ("hard-light", BlendMode::HardLight),
+    ("soft-light", BlendMode::SoftLight),
+    ("difference", BlendMode::Difference),
+    ("exclusion", BlendMode::Exclusion),
+    ("multiply", BlendMode::Multiply),
+    ("hue", BlendMode::Hue),
+    ("saturation", BlendMode::Saturation),
+    ("color", BlendMode::Color),
+    ("luminosity", BlendMode::Luminosity),
+];
+
+/// Index of a blend mode in `BLEND_MODES`
+pub fn blend_mode_index(mode: BlendMode) -> u8 {
+    BLEND_MODES
+        .iter()
+        .position(|&(_, m)| m == mode)
+        .expect("every blend mode is listed") as u8
+}
+
+/// Straight ARGB to premultiplied RGBA bytes, the layout of a tiny-skia pixmap
+#[inline]
+pub fn premultiply(argb: u32) -> [u8; 4] {
+    let a = argb >> 24;
+    let scale = |c: u32| ((c & 0xFF) * a + 127) / 255;
+    [
+        scale(argb >> 16) as u8,
+        scale(argb >> 8) as u8,
+        scale(argb) as u8,
+        a as u8,
+    ]
+}
+
+/// Premultiplied ARGB to straight ARGB; channels brighter than alpha are clamped
+#[inline]
+pub fn unpremultiply(argb: u32) -> u32 {
+    let a = argb >> 24;
+    if a == 0 {
+        return 0;
+    }
+    let scale = |c: u32| ((c & 0xFF) * 255 + a / 2) / a;
+    a << 24
+        | scale(argb >> 16).min(255) << 16
+        | scale(argb >> 8).min(255) << 8
+        | scale(argb).min(255)
+}
+
+/// Premultiplied color with components in 0..=1
+#[derive(Clone, Copy)]
+struct Color {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+}
+
+impl Color {
+    fn from_argb(argb: u32) -> Self {
+        let a = (argb >> 24) as f32 / 255.0;
+        let channel = |shift: u32| ((argb >> shift) & 0xFF) as f32 / 255.0 * a;
+        Self {
+            r: channel(16),
+            g: channel(8),
+            b: channel(0),
+            a,
+        }
+    }
+
+    fn to_argb(self) -> u32 {
+        let a = self.a.clamp(0.0, 1.0);
+        if a == 0.0 {
+            return 0;
+        }
+        let channel = |c: f32| ((c / a).clamp(0.0, 1.0) * 255.0).round() as u32;
+        ((a * 255.0).round() as u32) << 24
+            | channel(self.r) << 16
+            | channel(self.g) << 8
+            | channel(self.b)
+    }
+
+    /// Apply the same function to every channel, alpha included
+    fn map(s: Self, d: Self, f: impl Fn(f32, f32, f32, f32) -> f32) -> Self {
+        Self {
+            r: f(s.r, d.r, s.a, d.a),
+            g: f(s.g, d.g, s.a, d.a),
+            b: f(s.b, d.b, s.a, d.a),
+            a: f(s.a, d.a, s.a, d.a),
+        }
+    }
+
+    /// Apply a separable blend function to the color channels; alpha is source-over
+    fn separable(s: Self, d: Self, f: impl Fn(f32, f32, f32, f32) -> f32) -> Self {
+        Self {
+            r: f(s.r, d.r, s.a, d.a),
+            g: f(s.g, d.g, s.a, d.a),
+            b: f(s.b, d.b, s.a, d.a),
+            a: s.a + d.a - s.a * d.a,
+        }
+    }
+}
+
+/// Composite a straight ARGB source pixel onto a straight ARGB destination pixel
+pub fn blend(src: u32, dst: u32, mode: BlendMode) -> u32 {
+    match mode {
+        BlendMode::Source => return src,
+        BlendMode::Destination => return dst,
+        BlendMode::Clear => return 0,
+        BlendMode::SourceOver if src >> 24 == 0xFF => return src,
+        BlendMode::SourceOver if src >> 24 == 0 => return dst,
+        _ => {}
+    }
+
+    let s = Color::from_argb(src);
+    let d = Color::from_argb(dst);
+    let inv = |v: f32| 1.0 - v;
+
+    let result = match mode {
+        BlendMode::Clear | BlendMode::Source | BlendMode::Destination => unreachable!(),
+        BlendMode::SourceOver => Color::map(s, d, |s, d, sa, _| s + d * inv(sa)),
+        BlendMode::DestinationOver => Color::map(s, d, |s, d, _, da| d + s * inv(da)),
+        BlendMode::SourceIn => Color::map(s, d, |s, _, _, da| s * da),
+        BlendMode::DestinationIn => Color::map(s, d, |_, d, sa, _| d * sa),
+        BlendMode::SourceOut => Color::map(s, d, |s, _, _, da| s * inv(da)),
+        BlendMode::DestinationOut => Color::map(s, d, |_, d, sa, _| d * inv(sa)),
+        BlendMode::SourceAtop => Color::map(s, d, |s, d, sa, da| s * da + d * inv(sa)),
+        BlendMode::DestinationAtop => Color::map(s, d, |s, d, sa, da| d * sa + s * inv(da)),
+        BlendMode::Xor => Color::map(s, d, |s, d, sa, da| s * inv(da) + d * inv(sa)),
+        BlendMode::Plus => Color::map(s, d, |s, d, _, _| (s + d).min(1.0)),
+        BlendMode::Modulate => Color::map(s, d, |s, d, _, _| s * d),
+        BlendMode::Screen => Color::map(s, d, |s, d, _, _| s + d - s * d),
+        BlendMode::Overlay => Color::separable(s, d, |s, d, sa, da| hard_light(d, s, da, sa)),
+        BlendMode::Darken => Color::separable(s, d, |s, d, sa, da| s + d - (s * da).max(d * sa)),
+        BlendMode::Lighten => Color::separable(s, d, |s, d, sa, da| s + d - (s * da).min(d * sa)),
+        BlendMode::ColorDodge => Color::separable(s, d, |s, d, sa, da| {
+            if d == 0.0 {
+                s * inv(da)
+            } else if s >= sa {
+                s + d * inv(sa)
+            } else {
+                sa * da.min(d * sa / (sa - s)) + s * inv(da) + d * inv(sa)
+            }
+        }),
+        BlendMode::ColorBurn => Color::separable(s, d, |s, d, sa, da| {
+            if d >= da {
+                d + s * inv(da)
+            } else if s == 0.0 {
+                d * inv(sa)
+            } else {
+                sa * (da - da.min((da - d) * sa / s)) + s * inv(da) + d * inv(sa)
+            }
+        }),
+        BlendMode::HardLight => Color::separable(s, d, hard_light),
+        BlendMode::SoftLight => Color::separable(s, d, soft_light),
+        BlendMode::Difference => {
+            Color::separable(s, d, |s, d, sa, da| s + d - 2.0 * (s * da).min(d * sa))
+        }
+        BlendMode::Exclusion => Color::separable(s, d, |s, d, _, _| s + d - 2.0 * s * d),
+        BlendMode::Multiply => {
+            Color::separable(s, d, |s, d, sa, da| s * inv(da) + d * inv(sa) + s * d)
+        }
+        BlendMode::Hue | BlendMode::Saturation | BlendMode::Color | BlendMode::Luminosity => {
+            non_separable(s, d, mode)
+        }
+    };
+
+    result.to_argb()
+}
+
+fn hard_light(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    let inv = |v: f32| 1.0 - v;
+    let blended = if 2.0 * s <= sa {
+        2.0 * s * d
+    } else {
+        sa * da - 2.0 * (da - d) * (sa - s)
+    };
+    s * inv(da) + d * inv(sa) + blended
+}
+
+fn soft_light(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    let inv = |v: f32| 1.0 - v;
+    let m = if da > 0.0 { d / da } else { 0.0 };
+    let s2 = 2.0 * s;
+    let m4 = 4.0 * m;
+
+    let dark_src = d * (sa + (s2 - sa) * (1.0 - m));
+    let dark_dst = (m4 * m4 + m4) * (m - 1.0) + 7.0 * m;
+    let lite_dst = m.sqrt() - m;
+    let lite_src = d * sa + da * (s2 - sa) * if 4.0 * d <= da { dark_dst } else { lite_dst };
+
+    s * inv(da) + d * inv(sa) + if s2 <= sa { dark_src } else { lite_src }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    c[0] * 0.30 + c[1] * 0.59 + c[2] * 0.11
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let min = c[0].min(c[1]).min(c[2]);
+    let range = sat(c);
+    let scale = if range == 0.0 { 0.0 } else { s / range };
+    c.map(|v| (v - min) * scale)
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let diff = l - lum(c);
+    c.map(|v| v + diff)
+}
+
+/// Bring channels back into 0..=a, keeping luminosity
+///
+/// Mirrors tiny-skia, which only pulls negative channels up when the largest one is
+/// negative too, and otherwise just clamps them at zero.
+fn clip_color(c: [f32; 3], a: f32) -> [f32; 3] {
+    let min = c[0].min(c[1]).min(c[2]);
+    let max = c[0].max(c[1]).max(c[2]);
+    let l = lum(c);
+    c.map(|mut v| {
+        if max < 0.0 && l != min {
+            v = l + (v - l) * l / (l - min);
+        }
+        if max > a && max != l {
+            v = l + (v - l) * (a - l) / (max - l);
+        }
+        v.max(0.0)
+    })
+}
+
+/// Hue, saturation, color and luminosity, which mix channels rather than blending each
+fn non_separable(s: Color, d: Color, mode: BlendMode) -> Color {
+    let (sa, da) = (s.a, d.a);
+    let src = [s.r, s.g, s.b];
+    let dst = [d.r, d.g, d.b];
+    let scaled = |c: [f32; 3], k: f32| c.map(|v| v * k);
+
+    let mixed = match mode {
+        BlendMode::Hue => {
+            let c = set_sat(scaled(src, sa), sat(dst) * sa);
+            set_lum(c, lum(dst) * sa)
+        }
+        BlendMode::Saturation => {
+            let c = set_sat(scaled(dst, sa), sat(src) * da);
+            set_lum(c, lum(dst) * sa)
+        }
+        BlendMode::Color => set_lum(scaled(src, da), lum(dst) * sa),
+        _ => set_lum(scaled(dst, sa), lum(src) * da),
+    };
+    let mixed = clip_color(mixed, sa * da);
+
+    let channel = |i: usize| src[i] * (1.0 - da) + dst[i] * (1.0 - sa) + mixed[i];
+    Color {
+        r: channel(0),
+        g: channel(1),
+        b: channel(2),
+        a: sa + da - sa * da,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::window_manager::types::ColorFormat;
+    use tiny_skia::{Color as SkiaColor, Paint, Pixmap, PremultipliedColorU8, Rect, Transform};
+
+    #[test]
+    fn premultiplies_straight_alpha() {
+        assert_eq!(premultiply(0xFF336699), [0x33, 0x66, 0x99, 0xFF]);
+        assert_eq!(premultiply(0x00FFFFFF), [0, 0, 0, 0]);
+        assert_eq!(premultiply(0x80FFFFFF), [0x80, 0x80, 0x80, 0x80]);
+        // 0x40 * 0x80 / 255 = 32.13
+        assert_eq!(premultiply(0x80408000), [0x20, 0x40, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn premultiplied_channels_never_exceed_alpha() {
+        for a in 0..=255u32 {
+            for c in [0u32, 1, 127, 128, 254, 255] {
+                let [r, g, b, alpha] = premultiply(a << 24 | c << 16 | c << 8 | c);
+                assert_eq!(alpha as u32, a);
+                assert!(r <= alpha && g <= alpha && b <= alpha, "a={} c={}", a, c);
+            }
+        }
+    }
+
+    #[test]
+    fn premultiplied_input_round_trips() {
+        assert_eq!(ColorFormat::Straight.to_straight(0x80808080), 0x80808080);
+        assert_eq!(
+            ColorFormat::Premultiplied.to_straight(0x80808080),
+            0x80FFFFFF
+        );
+        assert_eq!(ColorFormat::Premultiplied.to_straight(0x00123456), 0);
+        // Channels brighter than alpha are not valid premultiplied colors and clamp
+        assert_eq!(unpremultiply(0x10FF0000), 0x10FF0000);
+
+        for a in 1..=255u32 {
+            for c in 0..=a {
+                let premultiplied = a << 24 | c << 16 | c << 8 | c;
+                let straight = unpremultiply(premultiplied);
+                let [r, g, b, alpha] = premultiply(straight);
+                assert_eq!([r, g, b, alpha], [c as u8, c as u8, c as u8, a as u8]);
+            }
+        }
+    }
+
+    /// Straight ARGB pairs covering opaque, translucent and fully transparent pixels
+    const SAMPLES: [(u32, u32); 8] = [
+        (0xFF336699, 0xFFCC8844),
+        (0x80FF0000, 0xFF0000FF),
+        (0xFF20E0A0, 0x80404040),
+        (0x40FFFFFF, 0xC0102030),
+        (0x00123456, 0xFF654321),
+        (0xFFABCDEF, 0x00000000),
+        (0xC0F08010, 0x60208040),
+        (0xFF000000, 0xFFFFFFFF),
+    ];
+
+    /// What tiny-skia draws for `src` over `dst`, as premultiplied RGBA
+    fn skia_blend(src: u32, dst: u32, mode: BlendMode) -> [u8; 4] {
+        let mut pixmap = Pixmap::new(1, 1).unwrap();
+        let [r, g, b, a] = premultiply(dst);
+        pixmap.pixels_mut()[0] = PremultipliedColorU8::from_rgba(r, g, b, a).unwrap();
+
+        let mut paint = Paint::default();
+        let channel = |shift: u32| (src >> shift) as u8;
+        paint.set_color(SkiaColor::from_rgba8(
+            channel(16),
+            channel(8),
+            channel(0),
+            channel(24),
+        ));
+        paint.blend_mode = mode;
+        paint.anti_alias = false;
+        // The float pipeline; the default 16-bit one rounds some modes by 2
+        paint.force_hq_pipeline = true;
+        let rect = Rect::from_xywh(0.0, 0.0, 1.0, 1.0).unwrap();
+        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+
+        let pixel = pixmap.pixels()[0];
+        [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+    }
+
+    #[test]
+    fn every_mode_matches_tiny_skia() {
+        for (name, mode) in BLEND_MODES {
+            for (src, dst) in SAMPLES {
+                let ours = premultiply(blend(src, dst, mode));
+                let skia = skia_blend(src, dst, mode);
+                let close = ours.iter().zip(&skia).all(|(&a, &b)| a.abs_diff(b) <= 1);
+                assert!(
+                    close,
+                    "{}: {:08X} onto {:08X} gave {:?}, tiny-skia {:?}",
+                    name, src, dst, ours, skia
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,834 @@
+//! Clipboard and primary selection access
+//!
+//! On X11 a selection belongs to a client, which has to answer every `SelectionRequest` for
+//! as long as it owns the selection. winit keeps those events to itself, so a background
+//! thread holds its own X connection with a hidden window. That window owns the selections
+//! written from JS and receives the conversions of selections owned by other programs.
+//! Contents written from JS are gone once the process exits.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::renderer::window_manager::types::{ClipboardImage, Selection};
+use crate::renderer::window_manager::utils::parse_selection;
+
+/// What this process has put on a selection
+#[derive(Clone, Debug)]
+enum Contents {
+    Text(String),
+    Png(Vec<u8>),
+}
+
+/// A format to ask the owner of a selection for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+    Utf8Text,
+    Latin1Text,
+    Png,
+}
+
+impl Contents {
+    /// The selection contents in a target format, if they can be converted to it
+    fn convert(&self, target: Target) -> Option<Vec<u8>> {
+        match (self, target) {
+            (Contents::Text(text), Target::Utf8Text) => Some(text.as_bytes().to_vec()),
+            (Contents::Text(text), Target::Latin1Text) => Some(
+                text.chars()
+                    .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                    .collect(),
+            ),
+            (Contents::Png(png), Target::Png) => Some(png.clone()),
+            _ => None,
+        }
+    }
+
+    /// Bytes in the largest form the contents can be converted to
+    fn len(&self) -> usize {
+        match self {
+            Contents::Text(text) => text.len(),
+            Contents::Png(png) => png.len(),
+        }
+    }
+}
+
+enum Request {
+    Read {
+        selection: Selection,
+        /// Formats to try, most preferred first
+        targets: &'static [Target],
+        reply: mpsc::Sender<Option<(Target, Vec<u8>)>>,
+    },
+    Write {
+        selection: Selection,
+        contents: Contents,
+        reply: mpsc::Sender<std::result::Result<(), String>>,
+    },
+}
+
+/// Sends requests to the clipboard thread
+struct RequestSender {
+    requests: mpsc::Sender<Request>,
+    /// Written to after each request to wake the thread while it waits on its X connection
+    #[cfg(target_os = "linux")]
+    wake: std::os::unix::net::UnixStream,
+}
+
+impl RequestSender {
+    /// False once the thread has stopped
+    fn send(&self, request: Request) -> bool {
+        if self.requests.send(request).is_err() {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            use std::io::Write;
+            // The socket is non-blocking; when it is full the thread is already awake
+            let _ = (&self.wake).write(&[0]);
+        }
+        true
+    }
+}
+
+/// Handle to the clipboard thread, which is started on first use
+#[derive(Default)]
+pub struct ClipboardConnection {
+    requests: Mutex<Option<RequestSender>>,
+}
+
+impl ClipboardConnection {
+    fn send(&self, request: Request) -> Result<()> {
+        let mut requests = self
+            .requests
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        let sender = match requests.as_ref() {
+            Some(sender) => sender,
+            None => requests.insert(spawn_clipboard_thread()?),
+        };
+        if !sender.send(request) {
+            *requests = None;
+            return Err(stopped_error());
+        }
+        Ok(())
+    }
+
+    fn read(
+        &self,
+        selection: Selection,
+        targets: &'static [Target],
+    ) -> Result<Option<(Target, Vec<u8>)>> {
+        let (reply, response) = mpsc::channel();
+        self.send(Request::Read {
+            selection,
+            targets,
+            reply,
+        })?;
+        response.recv().map_err(|_| stopped_error())
+    }
+
+    fn write(&self, selection: Selection, contents: Contents) -> Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.send(Request::Write {
+            selection,
+            contents,
+            reply,
+        })?;
+        response
+            .recv()
+            .map_err(|_| stopped_error())?
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e))
+    }
+}
+
+fn stopped_error() -> napi::Error {
+    napi::Error::new(
+        napi::Status::GenericFailure,
+        "The clipboard thread has stopped",
+    )
+}
+
+/// Clipboard and primary selection, from `WindowManager.clipboard`
+///
+/// Every method takes an optional `selection`: "clipboard" (the default) or "primary".
+/// Reads wait up to a second in total for the program that owns the selection to answer,
+/// and up to a second more for each chunk of contents too large to send at once.
+#[napi]
+pub struct Clipboard {
+    connection: Arc<ClipboardConnection>,
+}
+
+impl Clipboard {
+    pub(crate) fn new(connection: Arc<ClipboardConnection>) -> Self {
+        Self { connection }
+    }
+}
+
+#[napi]
+impl Clipboard {
+    /// Text on the selection, or null when it is empty or holds no text
+    #[napi]
+    pub fn read_text(&self, selection: Option<String>) -> Result<Option<String>> {
+        let selection = parse_selection(selection.as_deref())?;
+        let data = self
+            .connection
+            .read(selection, &[Target::Utf8Text, Target::Latin1Text])?;
+
+        Ok(data.map(|(target, bytes)| match target {
+            Target::Latin1Text => bytes.iter().map(|&b| char::from(b)).collect(),
+            _ => String::from_utf8_lossy(&bytes).into_owned(),
+        }))
+    }
+
+    /// Put text on the selection; throws if it is too large for the X server to send at once
+    #[napi]
+    pub fn write_text(&self, text: String, selection: Option<String>) -> Result<()> {
+        let selection = parse_selection(selection.as_deref())?;
+        self.connection.write(selection, Contents::Text(text))
+    }
+
+    /// Image on the selection as straight RGBA bytes, or null when it holds no PNG image
+    #[napi]
+    pub fn read_image(&self, selection: Option<String>) -> Result<Option<ClipboardImage>> {
+        let selection = parse_selection(selection.as_deref())?;
+        match self.connection.read(selection, &[Target::Png])? {
+            Some((_, png)) => decode_png(&png).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Put an image given as straight RGBA bytes on the selection, offered as PNG; throws if
+    /// the encoded image is too large for the X server to send at once
+    #[napi]
+    pub fn write_image(
+        &self,
+        rgba: Uint8Array,
+        width: u32,
+        height: u32,
+        selection: Option<String>,
+    ) -> Result<()> {
+        let selection = parse_selection(selection.as_deref())?;
+        let png = encode_png(&rgba, width, height)?;
+        self.connection.write(selection, Contents::Png(png))
+    }
+}
+
+/// Encode straight RGBA bytes as a PNG
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let expected = width as usize * height as usize * 4;
+    if rgba.len() != expected {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!(
+                "Expected {} bytes for a {}x{} RGBA image, got {}",
+                expected,
+                width,
+                height,
+                rgba.len()
+            ),
+        ));
+    }
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(|| {
+        napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Invalid image size {}x{}", width, height),
+        )
+    })?;
+
+    for (pixel, rgba) in pixmap.pixels_mut().iter_mut().zip(rgba.chunks_exact(4)) {
+        *pixel = tiny_skia::ColorU8::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3]).premultiply();
+    }
+    pixmap.encode_png().map_err(|e| {
+        napi::Error::new(
+            napi::Status::GenericFailure,
+            format!("Failed to encode image: {}", e),
+        )
+    })
+}
+
+/// Decode a PNG into straight RGBA bytes
+fn decode_png(png: &[u8]) -> Result<ClipboardImage> {
+    let pixmap = tiny_skia::Pixmap::decode_png(png).map_err(|e| {
+        napi::Error::new(
+            napi::Status::GenericFailure,
+            format!("Failed to decode clipboard image: {}", e),
+        )
+    })?;
+
+    let data: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Ok(ClipboardImage {
+        width: pixmap.width(),
+        height: pixmap.height(),
+        data: data.into(),
+    })
+}
+
+/// Start the thread that owns and converts selections
+fn spawn_clipboard_thread() -> Result<RequestSender> {
+    #[cfg(target_os = "linux")]
+    {
+        let (requests, receiver) = mpsc::channel();
+        let (started, startup) = mpsc::channel();
+        let (wake, woken) = x11_selection::wake_pair().map_err(|e| {
+            napi::Error::new(
+                napi::Status::GenericFailure,
+                format!("Failed to start the clipboard thread: {}", e),
+            )
+        })?;
+
+        thread::Builder::new()
+            .name("clipboard".into())
+            .spawn(
+                move || match unsafe { x11_selection::SelectionOwner::open() } {
+                    Ok(owner) => {
+                        let _ = started.send(Ok(()));
+                        owner.run(receiver, woken);
+                    }
+                    Err(e) => {
+                        let _ = started.send(Err(e));
+                    }
+                },
+            )
+            .map_err(|e| {
+                napi::Error::new(
+                    napi::Status::GenericFailure,
+                    format!("Failed to start the clipboard thread: {}", e),
+                )
+            })?;
+
+        startup
+            .recv()
+            .map_err(|_| stopped_error())?
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e))?;
+        Ok(RequestSender { requests, wake })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // TODO: Implement for Windows and macOS
+        Err(napi::Error::new(
+            napi::Status::GenericFailure,
+            "The clipboard is only supported on X11",
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11_selection {
+    use super::{Contents, Request, Target};
+    use crate::renderer::window_manager::types::Selection;
+    use std::collections::HashMap;
+    use std::io::{self, Read};
+    use std::os::fd::AsRawFd;
+    use std::os::raw::{c_int, c_long, c_ulong};
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+    use x11::xlib;
+
+    /// How long to wait for another program to hand over a selection, or the next chunk of
+    /// a large one
+    const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Property length to read at once, in 32-bit units; plenty for one transfer
+    const MAX_PROPERTY_LENGTH: c_long = 0x1FFF_FFFF;
+
+    struct Atoms {
+        clipboard: xlib::Atom,
+        targets: xlib::Atom,
+        utf8_string: xlib::Atom,
+        text: xlib::Atom,
+        text_plain_utf8: xlib::Atom,
+        png: xlib::Atom,
+        incr: xlib::Atom,
+        /// Property on our window that other programs write conversions to
+        transfer: xlib::Atom,
+    }
+
+    /// A hidden window on its own X connection that owns and converts selections
+    pub struct SelectionOwner {
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        atoms: Atoms,
+        owned: HashMap<Selection, Contents>,
+        /// Largest property we can write in one request; writing bigger contents fails
+        /// rather than sending them in chunks
+        max_property_size: usize,
+    }
+
+    impl SelectionOwner {
+        /// Connect to the X server and create the window
+        ///
+        /// # Safety
+        ///
+        /// The owner must stay on the thread that opened it.
+        pub unsafe fn open() -> Result<Self, String> {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return Err("Failed to open X11 display".into());
+            }
+
+            // Never mapped; it only exists to own selections and receive properties
+            let root = xlib::XDefaultRootWindow(display);
+            let window = xlib::XCreateSimpleWindow(display, root, 0, 0, 1, 1, 0, 0, 0);
+            xlib::XSelectInput(display, window, xlib::PropertyChangeMask);
+
+            let atom =
+                |name: &std::ffi::CStr| xlib::XInternAtom(display, name.as_ptr(), xlib::False);
+            let atoms = Atoms {
+                clipboard: atom(c"CLIPBOARD"),
+                targets: atom(c"TARGETS"),
+                utf8_string: atom(c"UTF8_STRING"),
+                text: atom(c"TEXT"),
+                text_plain_utf8: atom(c"text/plain;charset=utf-8"),
+                png: atom(c"image/png"),
+                incr: atom(c"INCR"),
+                transfer: atom(c"GPUI_SELECTION"),
+            };
+
+            let max_request = match xlib::XExtendedMaxRequestSize(display) {
+                0 => xlib::XMaxRequestSize(display),
+                size => size,
+            };
+            Ok(Self {
+                display,
+                window,
+                atoms,
+                owned: HashMap::new(),
+                max_property_size: (max_request as usize * 4).saturating_sub(1024),
+            })
+        }
+
+        /// Answer requests from JS until the `WindowManager` is dropped, sleeping until
+        /// either the X server or `woken` has something for us
+        pub fn run(mut self, requests: mpsc::Receiver<Request>, mut woken: UnixStream) {
+            let fds = [self.connection_fd(), woken.as_raw_fd()];
+            'run: loop {
+                while self.next_event(Instant::now()).is_some() {}
+
+                // Drain the wake-ups before the requests so none is missed in between
+                let mut buffer = [0; 64];
+                while woken.read(&mut buffer).is_ok_and(|read| read > 0) {}
+
+                loop {
+                    match requests.try_recv() {
+                        Ok(request) => self.handle(request),
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        Err(mpsc::TryRecvError::Disconnected) => break 'run,
+                    }
+                }
+
+                // Xlib may have read events off the socket while handling the requests
+                if unsafe { xlib::XPending(self.display) } == 0 {
+                    wait_readable(&fds, None);
+                }
+            }
+
+            unsafe {
+                xlib::XDestroyWindow(self.display, self.window);
+                xlib::XCloseDisplay(self.display);
+            }
+        }
+
+        fn handle(&mut self, request: Request) {
+            match request {
+                Request::Read {
+                    selection,
+                    targets,
+                    reply,
+                } => {
+                    let _ = reply.send(self.read(selection, targets));
+                }
+                Request::Write {
+                    selection,
+                    contents,
+                    reply,
+                } => {
+                    let _ = reply.send(self.write(selection, contents));
+                }
+            }
+        }
+
+        fn connection_fd(&self) -> c_int {
+            unsafe { xlib::XConnectionNumber(self.display) }
+        }
+
+        fn selection_atom(&self, selection: Selection) -> xlib::Atom {
+            match selection {
+                Selection::Clipboard => self.atoms.clipboard,
+                Selection::Primary => xlib::XA_PRIMARY,
+            }
+        }
+
+        fn selection_from_atom(&self, atom: xlib::Atom) -> Option<Selection> {
+            match atom {
+                atom if atom == self.atoms.clipboard => Some(Selection::Clipboard),
+                xlib::XA_PRIMARY => Some(Selection::Primary),
+                _ => None,
+            }
+        }
+
+        fn target_atom(&self, target: Target) -> xlib::Atom {
+            match target {
+                Target::Utf8Text => self.atoms.utf8_string,
+                Target::Latin1Text => xlib::XA_STRING,
+                Target::Png => self.atoms.png,
+            }
+        }
+
+        /// The format a requested target stands for, and the property type to answer with
+        fn target_from_atom(&self, atom: xlib::Atom) -> Option<(Target, xlib::Atom)> {
+            let atoms = &self.atoms;
+            match atom {
+                atom if atom == atoms.utf8_string || atom == atoms.text => {
+                    Some((Target::Utf8Text, atoms.utf8_string))
+                }
+                atom if atom == atoms.text_plain_utf8 => Some((Target::Utf8Text, atom)),
+                xlib::XA_STRING => Some((Target::Latin1Text, xlib::XA_STRING)),
+                atom if atom == atoms.png => Some((Target::Png, atom)),
+                _ => None,
+            }
+        }
+
+        fn write(&mut self, selection: Selection, contents: Contents) -> Result<(), String> {
+            if contents.len() > self.max_property_size {
+                return Err(format!(
+                    "Clipboard contents are {} bytes, more than the {} the X server accepts at once",
+                    contents.len(),
+                    self.max_property_size
+                ));
+            }
+            let atom = self.selection_atom(selection);
+            unsafe {
+                xlib::XSetSelectionOwner(self.display, atom, self.window, xlib::CurrentTime);
+                if xlib::XGetSelectionOwner(self.display, atom) != self.window {
+                    return Err("Could not take ownership of the selection".into());
+                }
+            }
+            self.owned.insert(selection, contents);
+            Ok(())
+        }
+
+        fn read(&mut self, selection: Selection, targets: &[Target]) -> Option<(Target, Vec<u8>)> {
+            if let Some(contents) = self.owned.get(&selection) {
+                return targets
+                    .iter()
+                    .find_map(|&target| contents.convert(target).map(|data| (target, data)));
+            }
+
+            // One deadline for all targets, so an owner that never answers costs one timeout
+            let deadline = Instant::now() + READ_TIMEOUT;
+            let selection = self.selection_atom(selection);
+            targets.iter().find_map(|&target| {
+                self.convert(selection, self.target_atom(target), deadline)
+                    .map(|data| (target, data))
+            })
+        }
+
+        /// Ask the owner of a selection to convert it and collect the result, giving up if
+        /// it has not answered by `deadline`
+        fn convert(
+            &mut self,
+            selection: xlib::Atom,
+            target: xlib::Atom,
+            deadline: Instant,
+        ) -> Option<Vec<u8>> {
+            unsafe {
+                xlib::XDeleteProperty(self.display, self.window, self.atoms.transfer);
+                xlib::XConvertSelection(
+                    self.display,
+                    selection,
+                    target,
+                    self.atoms.transfer,
+                    self.window,
+                    xlib::CurrentTime,
+                );
+                xlib::XFlush(self.display);
+            }
+
+            loop {
+                let event = self.next_event(deadline)?;
+                if event.get_type() == xlib::SelectionNotify {
+                    let notify = unsafe { event.selection };
+                    if notify.selection != selection {
+                        continue;
+                    }
+                    if notify.property == 0 {
+                        // The owner cannot convert to this target
+                        return None;
+                    }
+                    break;
+                }
+            }
+
+            let (property_type, data) = self.take_transfer_property()?;
+            if property_type != self.atoms.incr {
+                return Some(data);
+            }
+
+            // Large contents come in chunks: deleting the property asks for the next one,
+            // and an empty chunk ends the transfer
+            let mut data = Vec::new();
+            let mut deadline = Instant::now() + READ_TIMEOUT;
+            loop {
+                let event = self.next_event(deadline)?;
+                if event.get_type() != xlib::PropertyNotify {
+                    continue;
+                }
+                let property = unsafe { event.property };
+                if property.atom != self.atoms.transfer || property.state != xlib::PropertyNewValue
+                {
+                    continue;
+                }
+
+                let (_, chunk) = self.take_transfer_property()?;
+                if chunk.is_empty() {
+                    return Some(data);
+                }
+                data.extend_from_slice(&chunk);
+                deadline = Instant::now() + READ_TIMEOUT;
+            }
+        }
+
+        /// Read and delete the property conversions are written to
+        fn take_transfer_property(&self) -> Option<(xlib::Atom, Vec<u8>)> {
+            unsafe {
+                let mut property_type = 0;
+                let mut format = 0;
+                let mut items = 0;
+                let mut bytes_after = 0;
+                let mut data = std::ptr::null_mut();
+                let status = xlib::XGetWindowProperty(
+                    self.display,
+                    self.window,
+                    self.atoms.transfer,
+                    0,
+                    MAX_PROPERTY_LENGTH,
+                    xlib::True,
+                    xlib::AnyPropertyType as xlib::Atom,
+                    &mut property_type,
+                    &mut format,
+                    &mut items,
+                    &mut bytes_after,
+                    &mut data,
+                );
+                if status != xlib::Success as c_int || property_type == 0 {
+                    return None;
+                }
+
+                // Format 16 and 32 items are stored as C shorts and longs
+                let item_size = match format {
+                    8 => 1,
+                    16 => std::mem::size_of::<std::os::raw::c_short>(),
+                    32 => std::mem::size_of::<c_long>(),
+                    _ => 0,
+                };
+                let bytes = if data.is_null() {
+                    Vec::new()
+                } else {
+                    let bytes =
+                        std::slice::from_raw_parts(data, items as usize * item_size).to_vec();
+                    xlib::XFree(data as *mut _);
+                    bytes
+                };
+                Some((property_type, bytes))
+            }
+        }
+
+        /// Next event that is not a selection request or clear, answering those along the
+        /// way; `None` once `deadline` passes with no other event
+        fn next_event(&mut self, deadline: Instant) -> Option<xlib::XEvent> {
+            loop {
+                while unsafe { xlib::XPending(self.display) } > 0 {
+                    let mut event: xlib::XEvent = unsafe { std::mem::zeroed() };
+                    unsafe { xlib::XNextEvent(self.display, &mut event) };
+
+                    match event.get_type() {
+                        xlib::SelectionRequest => self.answer(unsafe { &event.selection_request }),
+                        xlib::SelectionClear => {
+                            let clear = unsafe { event.selection_clear };
+                            if let Some(selection) = self.selection_from_atom(clear.selection) {
+                                self.owned.remove(&selection);
+                            }
+                        }
+                        _ => return Some(event),
+                    }
+                }
+
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                wait_readable(&[self.connection_fd()], Some(deadline - now));
+            }
+        }
+
+        /// Convert a selection we own for another program
+        fn answer(&self, request: &xlib::XSelectionRequestEvent) {
+            // Clients older than ICCCM 2 leave the property unset and expect the target
+            let property = if request.property == 0 {
+                request.target
+            } else {
+                request.property
+            };
+            let contents = self
+                .selection_from_atom(request.selection)
+                .and_then(|selection| self.owned.get(&selection));
+            let stored = contents.is_some_and(|contents| {
+                self.store(request.requestor, property, request.target, contents)
+            });
+
+            let mut notify = xlib::XEvent {
+                selection: xlib::XSelectionEvent {
+                    type_: xlib::SelectionNotify,
+                    serial: 0,
+                    send_event: xlib::True,
+                    display: self.display,
+                    requestor: request.requestor,
+                    selection: request.selection,
+                    target: request.target,
+                    property: if stored { property } else { 0 },
+                    time: request.time,
+                },
+            };
+            unsafe {
+                xlib::XSendEvent(self.display, request.requestor, xlib::False, 0, &mut notify);
+                xlib::XFlush(self.display);
+            }
+        }
+
+        /// Write our contents to the requestor's property; false if the target is not
+        /// supported or the contents are too large for one request
+        fn store(
+            &self,
+            requestor: xlib::Window,
+            property: xlib::Atom,
+            target: xlib::Atom,
+            contents: &Contents,
+        ) -> bool {
+            if target == self.atoms.targets {
+                let mut targets: Vec<c_ulong> = vec![self.atoms.targets];
+                match contents {
+                    Contents::Text(_) => targets.extend([
+                        self.atoms.utf8_string,
+                        self.atoms.text_plain_utf8,
+                        self.atoms.text,
+                        xlib::XA_STRING,
+                    ]),
+                    Contents::Png(_) => targets.push(self.atoms.png),
+                }
+                unsafe {
+                    xlib::XChangeProperty(
+                        self.display,
+                        requestor,
+                        property,
+                        xlib::XA_ATOM,
+                        32,
+                        xlib::PropModeReplace,
+                        targets.as_ptr() as *const u8,
+                        targets.len() as c_int,
+                    );
+                }
+                return true;
+            }
+
+            let Some((target, property_type)) = self.target_from_atom(target) else {
+                return false;
+            };
+            let Some(data) = contents.convert(target) else {
+                return false;
+            };
+            if data.len() > self.max_property_size {
+                return false;
+            }
+            unsafe {
+                xlib::XChangeProperty(
+                    self.display,
+                    requestor,
+                    property,
+                    property_type,
+                    8,
+                    xlib::PropModeReplace,
+                    data.as_ptr(),
+                    data.len() as c_int,
+                );
+            }
+            true
+        }
+    }
+
+    /// The two ends of the socket that wakes the clipboard thread; the sending end never
+    /// blocks and the receiving end reads without waiting
+    pub fn wake_pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (wake, woken) = UnixStream::pair()?;
+        wake.set_nonblocking(true)?;
+        woken.set_nonblocking(true)?;
+        Ok((wake, woken))
+    }
+
+    /// Sleep until one of `fds` is readable or `timeout` passes; `None` waits indefinitely
+    fn wait_readable(fds: &[c_int], timeout: Option<Duration>) {
+        let mut poll_fds: Vec<libc::pollfd> = fds
+            .iter()
+            .map(|&fd| libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // Round up so a wait never ends just short of the deadline and spins
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout.as_micros().div_ceil(1000).min(c_int::MAX as u128) as c_int
+        });
+        unsafe {
+            libc::poll(
+                poll_fds.as_mut_ptr(),
+                poll_fds.len() as libc::nfds_t,
+                timeout,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trips_opaque_and_transparent_pixels() {
+        let rgba = [
+            255, 0, 0, 255, 0, 128, 255, 255, 0, 0, 0, 0, 10, 20, 30, 255,
+        ];
+        let png = encode_png(&rgba, 2, 2).unwrap();
+        let image = decode_png(&png).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(&image.data[..], &rgba[..]);
+    }
+
+    #[test]
+    fn encode_png_rejects_wrong_buffer_length() {
+        assert!(encode_png(&[0; 12], 2, 2).is_err());
+        assert!(encode_png(&[], 0, 0).is_err());
+    }
+
+    #[test]
+    fn text_converts_to_latin1_with_replacement() {
+        let contents = Contents::Text("café ✓".into());
+        assert_eq!(contents.convert(Target::Latin1Text).unwrap(), b"caf\xe9 ?");
+        assert_eq!(
+            contents.convert(Target::Utf8Text).unwrap(),
+            "café ✓".as_bytes()
+        );
+        assert!(contents.convert(Target::Png).is_none());
+    }
+}
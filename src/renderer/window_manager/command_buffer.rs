@@ -0,0 +1,1205 @@
+//! Batched drawing and window commands submitted from JS in a single call
+//!
+//! A batch is either an array of `BatchCommand` objects or a binary command buffer:
+//!
+//! ```text
+//! header:  magic "GPCB" (4 bytes) | version u16 | reserved u16
+//! then:    opcode u8 | payload, repeated until the end of the buffer
+//! ```
+//!
+//! All multi-byte values are little-endian, colors are ARGB `u32`. The opcodes and their
+//! payloads are documented in `docs/command_buffer.md`.
+
+use crate::renderer::window_manager::blend::{self, BLEND_MODES};
+use crate::renderer::window_manager::font;
+use crate::renderer::window_manager::types::Framebuffer;
+use crate::renderer::window_manager::utils::parse_blend_mode;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use tiny_skia::{BlendMode, Point, Transform};
+
+/// Magic bytes at the start of every binary command buffer
+pub const MAGIC: &[u8; 4] = b"GPCB";
+/// Binary command buffer version understood by this build
+pub const VERSION: u16 = 1;
+/// Size of the buffer header in bytes
+pub const HEADER_LEN: usize = 8;
+
+pub const OP_CLEAR: u8 = 0x01;
+pub const OP_SET_PIXEL: u8 = 0x02;
+pub const OP_FILL_RECT: u8 = 0x03;
+pub const OP_LINE: u8 = 0x04;
+pub const OP_BLIT: u8 = 0x05;
+pub const OP_TEXT: u8 = 0x06;
+pub const OP_PRESENT: u8 = 0x10;
+pub const OP_SET_POSITION: u8 = 0x11;
+pub const OP_SET_TITLE: u8 = 0x12;
+pub const OP_SET_TRANSFORM: u8 = 0x20;
+pub const OP_PUSH_CLIP: u8 = 0x21;
+pub const OP_POP_CLIP: u8 = 0x22;
+pub const OP_SET_BLEND_MODE: u8 = 0x23;
+
+/// A decoded batch operation
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawOp {
+    Clear {
+        color: u32,
+    },
+    SetPixel {
+        x: i32,
+        y: i32,
+        color: u32,
+    },
+    FillRect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: u32,
+    },
+    Line {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        color: u32,
+    },
+    /// Copy `width * height` ARGB pixels, row by row, with the top-left corner at (x, y)
+    Blit {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        pixels: Vec<u32>,
+    },
+    /// Draw text with the built-in 5x7 font, (x, y) being the top-left of the first glyph
+    Text {
+        x: i32,
+        y: i32,
+        color: u32,
+        text: String,
+    },
+    /// Replace the current transform, applied to the coordinates of later drawing operations
+    SetTransform {
+        transform: Transform,
+    },
+    /// Intersect the clip with a rectangle, given in the current transform's coordinates
+    PushClip {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    PopClip,
+    /// Set how later drawing operations composite onto the buffer
+    SetBlendMode {
+        mode: BlendMode,
+    },
+    Present,
+    SetPosition {
+        x: i32,
+        y: i32,
+    },
+    SetTitle {
+        title: String,
+    },
+}
+
+impl DrawOp {
+    /// Rewrite every color in the operation, including blitted pixels
+    pub fn map_colors(&mut self, f: impl Fn(u32) -> u32) {
+        match self {
+            DrawOp::Clear { color }
+            | DrawOp::SetPixel { color, .. }
+            | DrawOp::FillRect { color, .. }
+            | DrawOp::Line { color, .. }
+            | DrawOp::Text { color, .. } => *color = f(*color),
+            DrawOp::Blit { pixels, .. } => pixels.iter_mut().for_each(|px| *px = f(*px)),
+            DrawOp::SetTransform { .. }
+            | DrawOp::PushClip { .. }
+            | DrawOp::PopClip
+            | DrawOp::SetBlendMode { .. }
+            | DrawOp::Present
+            | DrawOp::SetPosition { .. }
+            | DrawOp::SetTitle { .. } => {}
+        }
+    }
+}
+
+/// A clip rectangle in buffer pixels, `x1`/`y1` exclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DeviceRect {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl DeviceRect {
+    fn intersect(self, other: DeviceRect) -> DeviceRect {
+        DeviceRect {
+            x0: self.x0.max(other.x0),
+            y0: self.y0.max(other.y0),
+            x1: self.x1.min(other.x1).max(self.x0.max(other.x0)),
+            y1: self.y1.min(other.y1).max(self.y0.max(other.y0)),
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+}
+
+/// Transform and clip state while a batch is applied to a framebuffer.
+///
+/// Batches draw in logical pixels: the base transform scales them to the framebuffer's
+/// physical pixels, and `setTransform` is applied on top of it. The state starts fresh for
+/// every batch, with the base transform, a clip covering the buffer and the "source" blend
+/// mode, which replaces pixels.
+pub struct DrawContext {
+    /// Logical to physical pixels
+    base: Transform,
+    transform: Transform,
+    /// Inverse of `transform`, `None` when it is degenerate and nothing can be drawn
+    inverse: Option<Transform>,
+    clips: Vec<DeviceRect>,
+    blend_mode: BlendMode,
+}
+
+impl DrawContext {
+    pub fn new(framebuffer: &Framebuffer) -> Self {
+        let scale = framebuffer.scale_factor as f32;
+        let base = Transform::from_scale(scale, scale);
+        Self {
+            base,
+            transform: base,
+            inverse: base.invert(),
+            clips: vec![DeviceRect {
+                x0: 0,
+                y0: 0,
+                x1: framebuffer.width.min(i32::MAX as u32) as i32,
+                y1: framebuffer.height.min(i32::MAX as u32) as i32,
+            }],
+            blend_mode: BlendMode::Source,
+        }
+    }
+
+    fn clip(&self) -> DeviceRect {
+        *self.clips.last().expect("base clip is never popped")
+    }
+
+    /// Apply one operation to the framebuffer; window operations are left to the caller
+    pub fn apply(&mut self, framebuffer: &mut Framebuffer, op: &DrawOp) {
+        match op {
+            DrawOp::Clear { color } => {
+                let clip = self.clip();
+                fill_device_rect(framebuffer, clip, *color, BlendMode::Source);
+            }
+            DrawOp::SetPixel { x, y, color } => {
+                self.fill_rect(framebuffer, *x as f32, *y as f32, 1.0, 1.0, *color)
+            }
+            DrawOp::FillRect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => self.fill_rect(
+                framebuffer,
+                *x as f32,
+                *y as f32,
+                *width as f32,
+                *height as f32,
+                *color,
+            ),
+            DrawOp::Line {
+                x0,
+                y0,
+                x1,
+                y1,
+                color,
+            } => self.line(framebuffer, (*x0, *y0), (*x1, *y1), *color),
+            DrawOp::Blit {
+                x,
+                y,
+                width,
+                height,
+                pixels,
+            } => {
+                let stride = *width as usize;
+                self.raster_rect(
+                    framebuffer,
+                    (*x as f32, *y as f32, *width as f32, *height as f32),
+                    |u, v| pixels[v as usize * stride + u as usize],
+                );
+            }
+            DrawOp::Text { x, y, color, text } => self.text(framebuffer, *x, *y, *color, text),
+            DrawOp::SetTransform { transform } => {
+                self.transform = self.base.pre_concat(*transform);
+                self.inverse = self.transform.invert();
+            }
+            DrawOp::PushClip {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let rect = self.device_bounds(*x as f32, *y as f32, *width as f32, *height as f32);
+                let clip = self.clip().intersect(rect);
+                self.clips.push(clip);
+            }
+            DrawOp::PopClip => {
+                if self.clips.len() > 1 {
+                    self.clips.pop();
+                }
+            }
+            DrawOp::SetBlendMode { mode } => self.blend_mode = *mode,
+            DrawOp::Present | DrawOp::SetPosition { .. } | DrawOp::SetTitle { .. } => {}
+        }
+    }
+
+    /// Buffer pixel containing the center of a pixel in transformed coordinates
+    fn map_pixel(&self, x: i32, y: i32) -> (i32, i32) {
+        let mut point = Point::from_xy(x as f32 + 0.5, y as f32 + 0.5);
+        self.transform.map_point(&mut point);
+        (point.x.floor() as i32, point.y.floor() as i32)
+    }
+
+    /// Bounding box in buffer pixels of a rectangle in transformed coordinates
+    fn device_bounds(&self, x: f32, y: f32, width: f32, height: f32) -> DeviceRect {
+        let mut corners = [
+            Point::from_xy(x, y),
+            Point::from_xy(x + width, y),
+            Point::from_xy(x, y + height),
+            Point::from_xy(x + width, y + height),
+        ];
+        self.transform.map_points(&mut corners);
+
+        let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for corner in corners {
+            x0 = x0.min(corner.x);
+            y0 = y0.min(corner.y);
+            x1 = x1.max(corner.x);
+            y1 = y1.max(corner.y);
+        }
+
+        // `as` saturates, so huge or infinite coordinates clamp rather than wrap
+        DeviceRect {
+            x0: x0.round() as i32,
+            y0: y0.round() as i32,
+            x1: x1.round() as i32,
+            y1: y1.round() as i32,
+        }
+    }
+
+    fn fill_rect(
+        &self,
+        framebuffer: &mut Framebuffer,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: u32,
+    ) {
+        if self.transform.has_skew() {
+            self.raster_rect(framebuffer, (x, y, width, height), |_, _| color);
+        } else {
+            let rect = self.device_bounds(x, y, width, height);
+            fill_device_rect(
+                framebuffer,
+                self.clip().intersect(rect),
+                color,
+                self.blend_mode,
+            );
+        }
+    }
+
+    /// Fill every buffer pixel whose center maps back into `rect`, coloring it with
+    /// `sample(u, v)` where (u, v) is the pixel's offset inside the rectangle
+    fn raster_rect(
+        &self,
+        framebuffer: &mut Framebuffer,
+        rect: (f32, f32, f32, f32),
+        sample: impl Fn(u32, u32) -> u32,
+    ) {
+        let Some(inverse) = self.inverse else {
+            return;
+        };
+        let (x, y, width, height) = rect;
+        let bounds = self.device_bounds(x, y, width, height);
+        // Widen by a pixel so centers on the rounded edges are still tested
+        let bounds = DeviceRect {
+            x0: bounds.x0.saturating_sub(1),
+            y0: bounds.y0.saturating_sub(1),
+            x1: bounds.x1.saturating_add(1),
+            y1: bounds.y1.saturating_add(1),
+        };
+        let area = self.clip().intersect(bounds);
+
+        for py in area.y0..area.y1 {
+            for px in area.x0..area.x1 {
+                let mut point = Point::from_xy(px as f32 + 0.5, py as f32 + 0.5);
+                inverse.map_point(&mut point);
+                let (u, v) = (point.x - x, point.y - y);
+                if u >= 0.0 && v >= 0.0 && u < width && v < height {
+                    let color = sample(u as u32, v as u32);
+                    framebuffer.blend_pixel(px as u32, py as u32, color, self.blend_mode);
+                }
+            }
+        }
+    }
+
+    /// One pixel wide line between two pixel centers (Bresenham)
+    fn line(&self, framebuffer: &mut Framebuffer, from: (i32, i32), to: (i32, i32), color: u32) {
+        let clip = self.clip();
+        let from = self.map_pixel(from.0, from.1);
+        let to = self.map_pixel(to.0, to.1);
+        // Only walk the visible part, however far apart the endpoints are
+        let Some(((x0, y0), (x1, y1))) = clip_segment(from, to, clip) else {
+            return;
+        };
+        // i64 so the error terms cannot overflow
+        let (mut cx, mut cy) = (x0 as i64, y0 as i64);
+        let (x1, y1) = (x1 as i64, y1 as i64);
+        let dx = (x1 - cx).abs();
+        let dy = -(y1 - cy).abs();
+        let step_x = if cx < x1 { 1 } else { -1 };
+        let step_y = if cy < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        loop {
+            if clip.contains(cx as i32, cy as i32) {
+                framebuffer.blend_pixel(cx as u32, cy as u32, color, self.blend_mode);
+            }
+            if cx == x1 && cy == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                cx += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                cy += step_y;
+            }
+        }
+    }
+
+    fn text(&self, framebuffer: &mut Framebuffer, x: i32, y: i32, color: u32, text: &str) {
+        let (mut pen_x, mut pen_y) = (x as f32, y as f32);
+
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = x as f32;
+                pen_y += font::LINE_HEIGHT as f32;
+                continue;
+            }
+
+            for (column, bits) in font::glyph(c).iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        self.fill_rect(
+                            framebuffer,
+                            pen_x + column as f32,
+                            pen_y + row as f32,
+                            1.0,
+                            1.0,
+                            color,
+                        );
+                    }
+                }
+            }
+            pen_x += font::ADVANCE as f32;
+        }
+    }
+}
+
+/// Clip a line between two pixel centers to `clip` (Liang–Barsky)
+///
+/// Segments that lie inside are returned unchanged; otherwise the endpoints move to the
+/// nearest pixels where the segment enters and leaves the clip.
+fn clip_segment(
+    from: (i32, i32),
+    to: (i32, i32),
+    clip: DeviceRect,
+) -> Option<((i32, i32), (i32, i32))> {
+    if clip.x0 >= clip.x1 || clip.y0 >= clip.y1 {
+        return None;
+    }
+    if clip.contains(from.0, from.1) && clip.contains(to.0, to.1) {
+        return Some((from, to));
+    }
+
+    let (x0, y0) = (from.0 as f64, from.1 as f64);
+    let (dx, dy) = (to.0 as f64 - x0, to.1 as f64 - y0);
+    let (min_x, max_x) = (clip.x0 as f64, (clip.x1 - 1) as f64);
+    let (min_y, max_y) = (clip.y0 as f64, (clip.y1 - 1) as f64);
+
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-dx, x0 - min_x),
+        (dx, max_x - x0),
+        (-dy, y0 - min_y),
+        (dy, max_y - y0),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+
+    let point = |t: f64| {
+        (
+            (x0 + t * dx).round().clamp(min_x, max_x) as i32,
+            (y0 + t * dy).round().clamp(min_y, max_y) as i32,
+        )
+    };
+    Some((point(t0), point(t1)))
+}
+
+/// Fill a rectangle already clipped to the buffer
+fn fill_device_rect(framebuffer: &mut Framebuffer, rect: DeviceRect, color: u32, mode: BlendMode) {
+    framebuffer.blend_rect(
+        rect.x0,
+        rect.y0,
+        (rect.x1 - rect.x0) as u32,
+        (rect.y1 - rect.y0) as u32,
+        color,
+        mode,
+    );
+}
+
+/// A batch command as a JS object, e.g. `{ op: "fillRect", x, y, width, height, r, g, b }`
+/// Supported ops: "clear", "setPixel", "fillRect", "setBlendMode", "present", "setPosition",
+/// "setTitle". Color channels default to 0 and alpha to 255. Lines, blits, text, transforms
+/// and clips are only available in binary command buffers
+#[napi(object)]
+pub struct BatchCommand {
+    pub op: String,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub r: Option<u8>,
+    pub g: Option<u8>,
+    pub b: Option<u8>,
+    pub a: Option<u8>,
+    pub title: Option<String>,
+    /// Blend mode name for "setBlendMode", e.g. "source-over" or "multiply"
+    pub blend_mode: Option<String>,
+}
+
+fn missing_field(index: usize, op: &str, field: &str) -> napi::Error {
+    napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Command {} ({}) is missing `{}`", index, op, field),
+    )
+}
+
+/// Convert JS batch command objects into operations
+pub fn decode_objects(commands: &[BatchCommand]) -> Result<Vec<DrawOp>> {
+    let mut ops = Vec::with_capacity(commands.len());
+
+    for (index, command) in commands.iter().enumerate() {
+        let op = command.op.as_str();
+        let x = || command.x.ok_or_else(|| missing_field(index, op, "x"));
+        let y = || command.y.ok_or_else(|| missing_field(index, op, "y"));
+        let color = ((command.a.unwrap_or(0xFF) as u32) << 24)
+            | ((command.r.unwrap_or(0) as u32) << 16)
+            | ((command.g.unwrap_or(0) as u32) << 8)
+            | (command.b.unwrap_or(0) as u32);
+
+        ops.push(match op {
+            "clear" => DrawOp::Clear { color },
+            "setPixel" => DrawOp::SetPixel {
+                x: x()?,
+                y: y()?,
+                color,
+            },
+            "fillRect" => DrawOp::FillRect {
+                x: x()?,
+                y: y()?,
+                width: command
+                    .width
+                    .ok_or_else(|| missing_field(index, op, "width"))?,
+                height: command
+                    .height
+                    .ok_or_else(|| missing_field(index, op, "height"))?,
+                color,
+            },
+            "setBlendMode" => DrawOp::SetBlendMode {
+                mode: parse_blend_mode(Some(
+                    command
+                        .blend_mode
+                        .as_deref()
+                        .ok_or_else(|| missing_field(index, op, "blendMode"))?,
+                ))?,
+            },
+            "present" => DrawOp::Present,
+            "setPosition" => DrawOp::SetPosition { x: x()?, y: y()? },
+            "setTitle" => DrawOp::SetTitle {
+                title: command
+                    .title
+                    .clone()
+                    .ok_or_else(|| missing_field(index, op, "title"))?,
+            },
+            _ => {
+                return Err(napi::Error::new(
+                    napi::Status::InvalidArg,
+                    format!("Command {} has unknown op `{}`", index, op),
+                ))
+            }
+        });
+    }
+
+    Ok(ops)
+}
+
+/// Bounds-checked little-endian reader over a command buffer
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                napi::Error::new(
+                    napi::Status::InvalidArg,
+                    format!(
+                        "Command buffer truncated: need {} bytes at offset {}, buffer is {}",
+                        len,
+                        self.offset,
+                        self.bytes.len()
+                    ),
+                )
+            })?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// A u32 byte length followed by that many bytes of UTF-8
+    fn string(&mut self, opcode_offset: usize) -> Result<String> {
+        let len = self.u32()? as usize;
+        let text = std::str::from_utf8(self.take(len)?).map_err(|_| {
+            napi::Error::new(
+                napi::Status::InvalidArg,
+                format!("String at offset {} is not valid UTF-8", opcode_offset),
+            )
+        })?;
+        Ok(text.to_string())
+    }
+}
+
+/// Decode a binary command buffer, validating the header and every payload length
+pub fn decode_buffer(bytes: &[u8]) -> Result<Vec<DrawOp>> {
+    let mut reader = Reader { bytes, offset: 0 };
+
+    if reader.take(4)? != MAGIC {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            "Command buffer does not start with the GPCB magic",
+        ));
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!(
+                "Unsupported command buffer version {} (expected {})",
+                version, VERSION
+            ),
+        ));
+    }
+    reader.u16()?; // reserved
+
+    let mut ops = Vec::new();
+    let mut clip_depth = 0usize;
+    while reader.offset < bytes.len() {
+        let opcode_offset = reader.offset;
+        let invalid = |message: &str| {
+            napi::Error::new(
+                napi::Status::InvalidArg,
+                format!("{} at offset {}", message, opcode_offset),
+            )
+        };
+
+        let op = match reader.u8()? {
+            OP_CLEAR => DrawOp::Clear {
+                color: reader.u32()?,
+            },
+            OP_SET_PIXEL => DrawOp::SetPixel {
+                x: reader.i32()?,
+                y: reader.i32()?,
+                color: reader.u32()?,
+            },
+            OP_FILL_RECT => DrawOp::FillRect {
+                x: reader.i32()?,
+                y: reader.i32()?,
+                width: reader.u32()?,
+                height: reader.u32()?,
+                color: reader.u32()?,
+            },
+            OP_LINE => DrawOp::Line {
+                x0: reader.i32()?,
+                y0: reader.i32()?,
+                x1: reader.i32()?,
+                y1: reader.i32()?,
+                color: reader.u32()?,
+            },
+            OP_BLIT => {
+                let x = reader.i32()?;
+                let y = reader.i32()?;
+                let width = reader.u32()?;
+                let height = reader.u32()?;
+                let len = (width as usize)
+                    .checked_mul(height as usize)
+                    .and_then(|count| count.checked_mul(4))
+                    .ok_or_else(|| invalid("Blit size overflows"))?;
+                let pixels = reader
+                    .take(len)?
+                    .chunks_exact(4)
+                    .map(|px| u32::from_le_bytes(px.try_into().unwrap()))
+                    .collect();
+                DrawOp::Blit {
+                    x,
+                    y,
+                    width,
+                    height,
+                    pixels,
+                }
+            }
+            OP_TEXT => DrawOp::Text {
+                x: reader.i32()?,
+                y: reader.i32()?,
+                color: reader.u32()?,
+                text: reader.string(opcode_offset)?,
+            },
+            OP_SET_TRANSFORM => {
+                let mut values = [0f32; 6];
+                for value in &mut values {
+                    *value = reader.f32()?;
+                }
+                let [sx, ky, kx, sy, tx, ty] = values;
+                let transform = Transform::from_row(sx, ky, kx, sy, tx, ty);
+                if !transform.is_finite() {
+                    return Err(invalid("Transform is not finite"));
+                }
+                DrawOp::SetTransform { transform }
+            }
+            OP_PUSH_CLIP => {
+                clip_depth += 1;
+                DrawOp::PushClip {
+                    x: reader.i32()?,
+                    y: reader.i32()?,
+                    width: reader.u32()?,
+                    height: reader.u32()?,
+                }
+            }
+            OP_POP_CLIP => {
+                clip_depth = clip_depth
+                    .checked_sub(1)
+                    .ok_or_else(|| invalid("popClip without a matching pushClip"))?;
+                DrawOp::PopClip
+            }
+            OP_SET_BLEND_MODE => {
+                let index = reader.u8()?;
+                let (_, mode) = BLEND_MODES
+                    .get(index as usize)
+                    .ok_or_else(|| invalid(&format!("Unknown blend mode {}", index)))?;
+                DrawOp::SetBlendMode { mode: *mode }
+            }
+            OP_PRESENT => DrawOp::Present,
+            OP_SET_POSITION => DrawOp::SetPosition {
+                x: reader.i32()?,
+                y: reader.i32()?,
+            },
+            OP_SET_TITLE => DrawOp::SetTitle {
+                title: reader.string(opcode_offset)?,
+            },
+            opcode => {
+                return Err(invalid(&format!("Unknown opcode 0x{:02x}", opcode)));
+            }
+        };
+        ops.push(op);
+    }
+
+    Ok(ops)
+}
+
+/// Encode operations as a binary command buffer, the inverse of `decode_buffer`
+pub fn encode(ops: &[DrawOp]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+
+    let mut put = |opcode: u8, fields: &[&[u8]]| {
+        bytes.push(opcode);
+        for field in fields {
+            bytes.extend_from_slice(field);
+        }
+    };
+
+    for op in ops {
+        match op {
+            DrawOp::Clear { color } => put(OP_CLEAR, &[&color.to_le_bytes()]),
+            DrawOp::SetPixel { x, y, color } => put(
+                OP_SET_PIXEL,
+                &[&x.to_le_bytes(), &y.to_le_bytes(), &color.to_le_bytes()],
+            ),
+            DrawOp::FillRect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => put(
+                OP_FILL_RECT,
+                &[
+                    &x.to_le_bytes(),
+                    &y.to_le_bytes(),
+                    &width.to_le_bytes(),
+                    &height.to_le_bytes(),
+                    &color.to_le_bytes(),
+                ],
+            ),
+            DrawOp::Line {
+                x0,
+                y0,
+                x1,
+                y1,
+                color,
+            } => put(
+                OP_LINE,
+                &[
+                    &x0.to_le_bytes(),
+                    &y0.to_le_bytes(),
+                    &x1.to_le_bytes(),
+                    &y1.to_le_bytes(),
+                    &color.to_le_bytes(),
+                ],
+            ),
+            DrawOp::Blit {
+                x,
+                y,
+                width,
+                height,
+                pixels,
+            } => {
+                let pixels: Vec<u8> = pixels.iter().flat_map(|px| px.to_le_bytes()).collect();
+                put(
+                    OP_BLIT,
+                    &[
+                        &x.to_le_bytes(),
+                        &y.to_le_bytes(),
+                        &width.to_le_bytes(),
+                        &height.to_le_bytes(),
+                        &pixels,
+                    ],
+                );
+            }
+            DrawOp::Text { x, y, color, text } => put(
+                OP_TEXT,
+                &[
+                    &x.to_le_bytes(),
+                    &y.to_le_bytes(),
+                    &color.to_le_bytes(),
+                    &(text.len() as u32).to_le_bytes(),
+                    text.as_bytes(),
+                ],
+            ),
+            DrawOp::SetTransform { transform } => {
+                let t = transform;
+                put(
+                    OP_SET_TRANSFORM,
+                    &[
+                        &t.sx.to_le_bytes(),
+                        &t.ky.to_le_bytes(),
+                        &t.kx.to_le_bytes(),
+                        &t.sy.to_le_bytes(),
+                        &t.tx.to_le_bytes(),
+                        &t.ty.to_le_bytes(),
+                    ],
+                );
+            }
+            DrawOp::PushClip {
+                x,
+                y,
+                width,
+                height,
+            } => put(
+                OP_PUSH_CLIP,
+                &[
+                    &x.to_le_bytes(),
+                    &y.to_le_bytes(),
+                    &width.to_le_bytes(),
+                    &height.to_le_bytes(),
+                ],
+            ),
+            DrawOp::PopClip => put(OP_POP_CLIP, &[]),
+            DrawOp::SetBlendMode { mode } => {
+                put(OP_SET_BLEND_MODE, &[&[blend::blend_mode_index(*mode)]])
+            }
+            DrawOp::Present => put(OP_PRESENT, &[]),
+            DrawOp::SetPosition { x, y } => {
+                put(OP_SET_POSITION, &[&x.to_le_bytes(), &y.to_le_bytes()])
+            }
+            DrawOp::SetTitle { title } => put(
+                OP_SET_TITLE,
+                &[&(title.len() as u32).to_le_bytes(), title.as_bytes()],
+            ),
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_op() -> Vec<DrawOp> {
+        vec![
+            DrawOp::Clear { color: 0xFF102030 },
+            DrawOp::SetTransform {
+                transform: Transform::from_row(2.0, 0.0, 0.5, 2.0, 10.0, -4.0),
+            },
+            DrawOp::PushClip {
+                x: -5,
+                y: 3,
+                width: 40,
+                height: 20,
+            },
+            DrawOp::SetPixel {
+                x: -1,
+                y: 7,
+                color: 0x80FFFFFF,
+            },
+            DrawOp::FillRect {
+                x: 1,
+                y: 2,
+                width: 3,
+                height: 4,
+                color: 0xFF00FF00,
+            },
+            DrawOp::Line {
+                x0: 0,
+                y0: 0,
+                x1: -9,
+                y1: 12,
+                color: 0xFFFF0000,
+            },
+            DrawOp::Blit {
+                x: 4,
+                y: 4,
+                width: 2,
+                height: 3,
+                pixels: vec![1, 2, 3, 4, 5, 6],
+            },
+            DrawOp::Text {
+                x: 0,
+                y: 10,
+                color: 0xFFFFFFFF,
+                text: "héllo\nworld".to_string(),
+            },
+            DrawOp::PopClip,
+            DrawOp::SetBlendMode {
+                mode: BlendMode::Multiply,
+            },
+            DrawOp::Present,
+            DrawOp::SetPosition { x: -100, y: 200 },
+            DrawOp::SetTitle {
+                title: "Frame 1".to_string(),
+            },
+        ]
+    }
+
+    fn pixel(framebuffer: &Framebuffer, x: u32, y: u32) -> u32 {
+        framebuffer.pixels[(y * framebuffer.width + x) as usize]
+    }
+
+    fn draw(width: u32, height: u32, ops: &[DrawOp]) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, height, true);
+        let mut context = DrawContext::new(&framebuffer);
+        for op in ops {
+            context.apply(&mut framebuffer, op);
+        }
+        framebuffer
+    }
+
+    #[test]
+    fn round_trips_every_opcode() {
+        let ops = every_op();
+        let bytes = encode(&ops);
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(decode_buffer(&bytes).unwrap(), ops);
+    }
+
+    #[test]
+    fn empty_buffer_has_only_a_header() {
+        let bytes = encode(&[]);
+        assert_eq!(bytes.len(), HEADER_LEN);
+        assert!(decode_buffer(&bytes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn every_truncation_is_rejected() {
+        let ops = every_op();
+        let bytes = encode(&ops);
+        // Offsets where a complete prefix of the operations ends are valid buffers
+        let boundaries: Vec<usize> = (0..=ops.len())
+            .map(|count| encode(&ops[..count]).len())
+            .collect();
+
+        for len in 0..bytes.len() {
+            let result = decode_buffer(&bytes[..len]);
+            if boundaries.contains(&len) {
+                assert!(result.is_ok(), "prefix of {} bytes should decode", len);
+            } else {
+                assert!(
+                    result.is_err(),
+                    "prefix of {} bytes should be rejected",
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let mut bytes = encode(&[DrawOp::Present]);
+        bytes[0] = b'X';
+        assert!(decode_buffer(&bytes).is_err());
+
+        let mut bytes = encode(&[DrawOp::Present]);
+        bytes[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(decode_buffer(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_lengths_without_allocating() {
+        let mut bytes = encode(&[]);
+        bytes.push(OP_BLIT);
+        for value in [0i32.to_le_bytes(), 0i32.to_le_bytes()] {
+            bytes.extend_from_slice(&value);
+        }
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_buffer(&bytes).is_err());
+
+        let mut bytes = encode(&[]);
+        bytes.push(OP_SET_TITLE);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_buffer(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        let mut bytes = encode(&[]);
+        bytes.push(0xEE);
+        assert!(decode_buffer(&bytes).is_err());
+
+        assert!(decode_buffer(&encode(&[DrawOp::PopClip])).is_err());
+
+        let transform = Transform::from_row(f32::NAN, 0.0, 0.0, 1.0, 0.0, 0.0);
+        assert!(decode_buffer(&encode(&[DrawOp::SetTransform { transform }])).is_err());
+
+        let mut bytes = encode(&[]);
+        bytes.extend_from_slice(&[OP_SET_BLEND_MODE, BLEND_MODES.len() as u8]);
+        assert!(decode_buffer(&bytes).is_err());
+    }
+
+    #[test]
+    fn transform_and_clip_apply_to_fills() {
+        let framebuffer = draw(
+            8,
+            8,
+            &[
+                DrawOp::SetTransform {
+                    transform: Transform::from_translate(2.0, 1.0),
+                },
+                DrawOp::PushClip {
+                    x: 0,
+                    y: 0,
+                    width: 3,
+                    height: 3,
+                },
+                DrawOp::FillRect {
+                    x: 0,
+                    y: 0,
+                    width: 8,
+                    height: 8,
+                    color: 0xFFFFFFFF,
+                },
+                DrawOp::PopClip,
+                DrawOp::SetPixel {
+                    x: 5,
+                    y: 6,
+                    color: 0xFF0000FF,
+                },
+            ],
+        );
+
+        for y in 0..8 {
+            for x in 0..7 {
+                let expected = if (2..5).contains(&x) && (1..4).contains(&y) {
+                    0xFFFFFFFF
+                } else {
+                    0
+                };
+                assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+        assert_eq!(pixel(&framebuffer, 7, 7), 0xFF0000FF);
+    }
+
+    #[test]
+    fn batches_draw_in_logical_pixels() {
+        let mut framebuffer = Framebuffer::new(2, 2, true);
+        framebuffer.rescale(2, 2, 2.0);
+        let mut context = DrawContext::new(&framebuffer);
+        context.apply(
+            &mut framebuffer,
+            &DrawOp::SetPixel {
+                x: 1,
+                y: 0,
+                color: 7,
+            },
+        );
+
+        assert_eq!((framebuffer.width, framebuffer.height), (4, 4));
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x >= 2 && y < 2 { 7 } else { 0 };
+                assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn far_apart_line_endpoints_are_clipped_first() {
+        let line = |from: (i32, i32), to: (i32, i32)| DrawOp::Line {
+            x0: from.0,
+            y0: from.1,
+            x1: to.0,
+            y1: to.1,
+            color: 0xFFFFFFFF,
+        };
+        let framebuffer = draw(
+            8,
+            8,
+            &[
+                line((i32::MIN, i32::MIN), (i32::MAX, i32::MAX)),
+                line((i32::MIN, 2), (i32::MAX, 2)),
+                line((i32::MIN, -5), (i32::MAX, -5)),
+            ],
+        );
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if x == y || y == 2 { 0xFFFFFFFF } else { 0 };
+                assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn blit_and_line_land_on_expected_pixels() {
+        let framebuffer = draw(
+            6,
+            6,
+            &[
+                DrawOp::Blit {
+                    x: 1,
+                    y: 1,
+                    width: 2,
+                    height: 2,
+                    pixels: vec![1, 2, 3, 4],
+                },
+                DrawOp::Line {
+                    x0: 0,
+                    y0: 5,
+                    x1: 5,
+                    y1: 5,
+                    color: 9,
+                },
+            ],
+        );
+
+        assert_eq!(pixel(&framebuffer, 1, 1), 1);
+        assert_eq!(pixel(&framebuffer, 2, 1), 2);
+        assert_eq!(pixel(&framebuffer, 1, 2), 3);
+        assert_eq!(pixel(&framebuffer, 2, 2), 4);
+        assert!((0..6).all(|x| pixel(&framebuffer, x, 5) == 9));
+        assert_eq!(pixel(&framebuffer, 0, 0), 0);
+    }
+
+    #[test]
+    fn blend_mode_applies_to_later_drawing() {
+        let fill = |x, color| DrawOp::FillRect {
+            x,
+            y: 0,
+            width: 1,
+            height: 1,
+            color,
+        };
+        let framebuffer = draw(
+            3,
+            1,
+            &[
+                DrawOp::Clear { color: 0xFFFF0000 },
+                fill(0, 0x800000FF),
+                DrawOp::SetBlendMode {
+                    mode: BlendMode::SourceOver,
+                },
+                fill(1, 0x800000FF),
+                DrawOp::SetBlendMode {
+                    mode: BlendMode::Multiply,
+                },
+                fill(2, 0xFF00FF00),
+            ],
+        );
+
+        assert_eq!(pixel(&framebuffer, 0, 0), 0x800000FF);
+        assert_eq!(pixel(&framebuffer, 1, 0), 0xFF7F0080);
+        assert_eq!(pixel(&framebuffer, 2, 0), 0xFF000000);
+    }
+}
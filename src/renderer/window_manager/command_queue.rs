@@ -0,0 +1,221 @@
+use crate::renderer::window_manager::types::{UserEvent, WindowCommand};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use winit::event_loop::EventLoopProxy;
+
+/// Wakes the event loop when new work is queued from JS.
+///
+/// Wakeups are coalesced: only the first command after the event loop last drained the
+/// queue sends an event through the proxy, so a burst of commands costs one wakeup.
+#[derive(Default)]
+pub struct EventLoopWaker {
+    proxy: OnceLock<EventLoopProxy<UserEvent>>,
+    wake_pending: AtomicBool,
+}
+
+impl EventLoopWaker {
+    /// Attach the proxy of the running event loop
+    pub fn set_proxy(&self, proxy: EventLoopProxy<UserEvent>) {
+        let _ = self.proxy.set(proxy);
+    }
+
+    /// Request a wakeup unless one is already on its way
+    pub fn wake(&self) {
+        if self.wake_pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(proxy) = self.proxy.get() {
+            // Fails only once the event loop has exited
+            let _ = proxy.send_event(UserEvent::Wake);
+        }
+    }
+
+    /// Called by the event loop before it drains the queue
+    pub fn clear(&self) {
+        self.wake_pending.store(false, Ordering::Release);
+    }
+}
+
+/// Sending half of the command queue, owned by `WindowManager`
+#[derive(Clone)]
+pub struct CommandSender {
+    sender: mpsc::Sender<WindowCommand>,
+    waker: Arc<EventLoopWaker>,
+}
+
+impl CommandSender {
+    /// Queue a command and wake the event loop to process it
+    pub fn send(&self, command: WindowCommand) {
+        // The receiver only goes away when the event loop thread has exited
+        let _ = self.sender.send(command);
+        self.waker.wake();
+    }
+
+    /// Wake the event loop without queueing a command, e.g. after writing pixels
+    pub fn wake(&self) {
+        self.waker.wake();
+    }
+
+    pub fn waker(&self) -> &Arc<EventLoopWaker> {
+        &self.waker
+    }
+}
+
+/// Receiving half of the command queue, owned by `WindowManagerApp`
+pub struct CommandReceiver {
+    receiver: mpsc::Receiver<WindowCommand>,
+    waker: Arc<EventLoopWaker>,
+}
+
+impl CommandReceiver {
+    /// Take every command queued so far, in submission order
+    pub fn drain(&self) -> Vec<WindowCommand> {
+        // Clear first so that commands sent while draining trigger a new wakeup
+        self.waker.clear();
+        self.receiver.try_iter().collect()
+    }
+}
+
+/// Create a connected command sender/receiver pair
+pub fn command_queue() -> (CommandSender, CommandReceiver) {
+    let (sender, receiver) = mpsc::channel();
+    let waker = Arc::new(EventLoopWaker::default());
+
+    (
+        CommandSender {
+            sender,
+            waker: waker.clone(),
+        },
+        CommandReceiver { receiver, waker },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const COMMANDS_PER_FRAME: u64 = 10_000;
+    const FRAMES: u64 = 100;
+
+    fn present(window_id: u64) -> WindowCommand {
+        WindowCommand::Present { window_id }
+    }
+
+    fn window_id(command: &WindowCommand) -> u64 {
+        match command {
+            WindowCommand::Present { window_id } => *window_id,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn drain_preserves_submission_order() {
+        let (sender, receiver) = command_queue();
+        for id in 0..100 {
+            sender.send(present(id));
+        }
+
+        let ids: Vec<u64> = receiver.drain().iter().map(window_id).collect();
+        assert_eq!(ids, (0..100).collect::<Vec<_>>());
+        assert!(receiver.drain().is_empty());
+    }
+
+    #[test]
+    fn wakeups_are_coalesced_until_drained() {
+        let (sender, receiver) = command_queue();
+        let waker = sender.waker().clone();
+
+        sender.send(present(1));
+        sender.send(present(2));
+        assert!(waker.wake_pending.load(Ordering::Acquire));
+
+        receiver.drain();
+        assert!(!waker.wake_pending.load(Ordering::Acquire));
+
+        sender.send(present(3));
+        assert!(waker.wake_pending.load(Ordering::Acquire));
+    }
+
+    /// Throughput of 10k commands per frame from a JS-like producer thread while the event
+    /// loop keeps presenting a 1080p buffer, comparing the old single `Mutex` (queue and
+    /// pixels under one lock) with the channel plus a per-window pixel lock. The slowest
+    /// frame shows how long JS can be stalled behind a present.
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_command_throughput_10k_per_frame() {
+        const PIXELS: usize = 1920 * 1080;
+
+        /// Returns (total time, slowest batch of COMMANDS_PER_FRAME sends)
+        fn run(
+            send: impl Fn(u64) + Send + 'static,
+            mut frame: impl FnMut(),
+        ) -> (Duration, Duration) {
+            let start = Instant::now();
+            let producer = thread::spawn(move || {
+                let mut slowest = Duration::ZERO;
+                for frame in 0..FRAMES {
+                    let batch_start = Instant::now();
+                    for _ in 0..COMMANDS_PER_FRAME {
+                        send(frame);
+                    }
+                    slowest = slowest.max(batch_start.elapsed());
+                }
+                slowest
+            });
+            while !producer.is_finished() {
+                frame();
+                thread::sleep(Duration::from_millis(1));
+            }
+            let slowest = producer.join().unwrap();
+            (start.elapsed(), slowest)
+        }
+
+        // Old design: commands and pixels share one lock, and presenting clones the pixels
+        let shared = Arc::new(Mutex::new((Vec::new(), vec![0u32; PIXELS])));
+        let producer_shared = shared.clone();
+        let (mutex_time, mutex_slowest) = run(
+            move |frame| producer_shared.lock().unwrap().0.push(present(frame)),
+            || {
+                let mut state = shared.lock().unwrap();
+                std::mem::take(&mut state.0);
+                let pixels = state.1.clone();
+                let rgba: Vec<u8> = pixels.iter().flat_map(|argb| argb.to_le_bytes()).collect();
+                std::hint::black_box(rgba);
+            },
+        );
+
+        // New design: commands flow through the channel, pixels have their own lock
+        let (sender, receiver) = command_queue();
+        let framebuffer = Mutex::new(vec![0u32; PIXELS]);
+        let mut rgba = vec![0u8; PIXELS * 4];
+        let (channel_time, channel_slowest) = run(
+            move |frame| sender.send(present(frame)),
+            || {
+                receiver.drain();
+                let pixels = framebuffer.lock().unwrap();
+                for (dst, &argb) in rgba.chunks_exact_mut(4).zip(pixels.iter()) {
+                    dst.copy_from_slice(&argb.to_le_bytes());
+                }
+                std::hint::black_box(&rgba);
+            },
+        );
+
+        let total = (COMMANDS_PER_FRAME * FRAMES) as f64;
+        for (name, time, slowest) in [
+            ("Mutex<Vec> + shared pixels", mutex_time, mutex_slowest),
+            ("channel + per-window pixels", channel_time, channel_slowest),
+        ] {
+            println!(
+                "{}: {:.0} commands/s, slowest 10k-command frame {:?}",
+                name,
+                total / time.as_secs_f64(),
+                slowest,
+            );
+        }
+    }
+}
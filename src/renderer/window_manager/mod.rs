@@ -1,7 +1,14 @@
 pub mod api;
 pub mod app;
+pub mod blend;
+pub mod clipboard;
+pub mod command_buffer;
+pub mod command_queue;
+pub mod font;
 pub mod types;
 pub mod utils;
+pub mod window;
+pub mod x11_utils;
 
 // Re-export the main public API
 pub use api::WindowManager;
@@ -1,6 +1,14 @@
-use std::collections::HashMap;
+use crate::renderer::window_manager::blend;
+use crate::renderer::window_manager::command_buffer::DrawOp;
+use napi::bindgen_prelude::{FnArgs, Uint8Array};
+use napi::threadsafe_function::ThreadsafeFunction;
+use napi_derive::napi;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
-use winit::window::WindowId;
+use std::time::{Duration, Instant};
+use tiny_skia::BlendMode;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::{CursorGrabMode, CursorIcon, Icon, UserAttentionType, WindowId};
 
 /// Shared state between the window manager and the event loop
 pub type SharedState = Arc<Mutex<WindowManagerState>>;
@@ -8,25 +16,713 @@ pub type SharedState = Arc<Mutex<WindowManagerState>>;
 /// Window manager state that can be accessed from both JS and the event loop
 pub struct WindowManagerState {
     pub windows: HashMap<u64, WindowState>,
-    pub pending_commands: Vec<WindowCommand>,
+    pub frame_requests: Vec<FrameRequest>,
+    pub next_frame_request_id: u32,
     pub should_exit: bool,
+    /// Connected monitors, refreshed by the event loop when it starts and when a window's
+    /// scale factor changes, so `getMonitors` does not have to wait for the event loop
+    pub monitors: Vec<MonitorInfo>,
+}
+
+impl WindowManagerState {
+    /// Look up a window, failing with a clear error for ids that were never created or
+    /// have been closed
+    pub fn window(&self, window_id: u64) -> napi::Result<&WindowState> {
+        self.windows
+            .get(&window_id)
+            .ok_or_else(|| unknown_window(window_id))
+    }
+
+    /// Mutable variant of `window`
+    pub fn window_mut(&mut self, window_id: u64) -> napi::Result<&mut WindowState> {
+        self.windows
+            .get_mut(&window_id)
+            .ok_or_else(|| unknown_window(window_id))
+    }
+
+    /// An open modal child that blocks input to `parent`, if any
+    pub fn blocking_child(&self, parent: u64) -> Option<u64> {
+        self.windows
+            .iter()
+            .find(|(_, ws)| ws.parent == Some(parent) && ws.blocks_parent_input)
+            .map(|(id, _)| *id)
+    }
+
+    /// The input region a window should have; not accepting input (`setIgnoreInput` or a
+    /// blocking modal child) wins over alpha hit testing
+    pub fn input_region(&self, window_id: u64) -> Option<InputRegion> {
+        let window_state = self.windows.get(&window_id)?;
+        if window_state.ignore_input || self.blocking_child(window_id).is_some() {
+            Some(InputRegion::Empty)
+        } else if let Some(threshold) = window_state.alpha_hit_threshold {
+            Some(InputRegion::Alpha(threshold))
+        } else {
+            Some(InputRegion::Whole)
+        }
+    }
+}
+
+/// Where a window accepts pointer input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputRegion {
+    /// Input passes through the whole window
+    Empty,
+    /// Only pixels with at least this alpha accept input
+    Alpha(u8),
+    /// The whole window accepts input
+    Whole,
+}
+
+fn unknown_window(window_id: u64) -> napi::Error {
+    napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Window {} does not exist", window_id),
+    )
+}
+
+/// Events delivered to the event loop through its `EventLoopProxy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserEvent {
+    /// Commands or pixel data were queued from JS and should be processed right away
+    Wake,
+}
+
+/// JS callback for `requestAnimationFrame`, called with (timestamp ms, frame delta ms)
+pub type FrameCallback =
+    ThreadsafeFunction<FnArgs<(f64, f64)>, (), FnArgs<(f64, f64)>, napi::Status, false>;
+
+/// A pending animation frame callback for a window
+pub struct FrameRequest {
+    pub id: u32,
+    pub window_id: u64,
+    pub callback: FrameCallback,
 }
 
 /// State for each managed window
 pub struct WindowState {
     pub width: u32,
     pub height: u32,
-    pub pixel_buffer: Vec<u32>,
+    pub framebuffer: SharedFramebuffer,
     pub needs_redraw: bool,
     pub title: String,
     pub x: Option<i32>,
     pub y: Option<i32>,
     pub always_on_top: bool,
     pub transparent: bool,
+    /// Whether the window got an ARGB visual and a compositor is running, so transparent
+    /// pixels really show what is behind the window; set once the window is created
+    pub supports_transparency: bool,
     pub decorations: bool,
+    /// When set, input passes through pixels whose alpha is below this threshold
+    pub alpha_hit_threshold: Option<u8>,
+    /// Whether input passes through the whole window, from `setIgnoreInput`
+    pub ignore_input: bool,
+    /// Encoding of colors passed to the pixel APIs for this window
+    pub color_format: ColorFormat,
+    /// Whole-window opacity from 0.0 to 1.0, updated as fades progress
+    pub opacity: f64,
+    /// The window this one is transient for; closing the parent closes this window too
+    pub parent: Option<u64>,
+    /// Whether this is a modal child that keeps focus away from its parent
+    pub blocks_parent_input: bool,
+    /// Limits on the logical size the window, and so its framebuffer, can be resized to
+    pub size_constraints: SizeConstraints,
+    pub stats: FrameStats,
+    /// What the window system reports for the window, as opposed to what was requested
+    pub native: NativeWindowState,
     pub winit_id: Option<WindowId>,
 }
 
+/// Native window state, refreshed by the event loop from winit events and queries
+#[derive(Clone, Copy, Debug)]
+pub struct NativeWindowState {
+    /// `None` until the window exists, or where the platform cannot report it
+    pub outer_position: Option<PhysicalPosition<i32>>,
+    pub inner_size: PhysicalSize<u32>,
+    pub outer_size: PhysicalSize<u32>,
+    pub focused: bool,
+    pub visible: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub scale_factor: f64,
+}
+
+impl NativeWindowState {
+    /// Placeholder for a window that has been requested but not created yet
+    pub fn requested(width: u32, height: u32) -> Self {
+        let size = PhysicalSize::new(width, height);
+        Self {
+            outer_position: None,
+            inner_size: size,
+            outer_size: size,
+            focused: false,
+            visible: true,
+            minimized: false,
+            maximized: false,
+            scale_factor: 1.0,
+        }
+    }
+
+    /// Read the current state of a native window
+    pub fn query(window: &winit::window::Window) -> Self {
+        Self {
+            outer_position: window.outer_position().ok(),
+            inner_size: window.inner_size(),
+            outer_size: window.outer_size(),
+            focused: window.has_focus(),
+            visible: window.is_visible().unwrap_or(true),
+            minimized: window.is_minimized().unwrap_or(false),
+            maximized: window.is_maximized(),
+            scale_factor: window.scale_factor(),
+        }
+    }
+
+    /// Outer position in logical pixels, the same units as `setPosition`
+    pub fn position(&self) -> Option<WindowPosition> {
+        self.outer_position.map(|position| {
+            let position = position.to_logical::<i32>(self.scale_factor);
+            WindowPosition {
+                x: position.x,
+                y: position.y,
+            }
+        })
+    }
+
+    /// Size in logical pixels
+    pub fn logical_size(&self, size: PhysicalSize<u32>) -> WindowSize {
+        let size = size.to_logical::<u32>(self.scale_factor);
+        WindowSize {
+            width: size.width,
+            height: size.height,
+        }
+    }
+}
+
+/// A window position in logical pixels
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A window size in logical pixels
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Size limits for a window, in logical pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeConstraints {
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    /// Steps the size changes by, counted from `min_size`
+    pub resize_increments: Option<(u32, u32)>,
+    /// Width to height ratio the window is locked to
+    pub aspect_ratio: Option<(u32, u32)>,
+}
+
+impl SizeConstraints {
+    /// The largest size within the constraints that fits in `width` x `height`, or the
+    /// minimum size when nothing fits
+    pub fn clamp(&self, width: u32, height: u32) -> (u32, u32) {
+        let (min_width, min_height) = self.min_size.unwrap_or((1, 1));
+        let (max_width, max_height) = self.max_size.unwrap_or((u32::MAX, u32::MAX));
+        let mut width = width.clamp(min_width, max_width.max(min_width));
+        let mut height = height.clamp(min_height, max_height.max(min_height));
+
+        if let Some((aspect_width, aspect_height)) = self.aspect_ratio {
+            // Shrink whichever side is too long for the ratio, growing the other side
+            // instead when shrinking would go below the minimum
+            let (w, h) = (width as u64, height as u64);
+            let (aspect_width, aspect_height) = (aspect_width as u64, aspect_height as u64);
+            if w * aspect_height > h * aspect_width {
+                width = (h * aspect_width / aspect_height) as u32;
+                if width < min_width {
+                    width = min_width;
+                    let grown = (min_width as u64 * aspect_height).div_ceil(aspect_width);
+                    height = grown.min(max_height.max(min_height) as u64) as u32;
+                }
+            } else {
+                height = (w * aspect_height / aspect_width) as u32;
+                if height < min_height {
+                    height = min_height;
+                    let grown = (min_height as u64 * aspect_width).div_ceil(aspect_height);
+                    width = grown.min(max_width.max(min_width) as u64) as u32;
+                }
+            }
+        }
+
+        if let Some((width_inc, height_inc)) = self.resize_increments {
+            let base_width = min_width.min(width);
+            let base_height = min_height.min(height);
+            width = base_width + (width - base_width) / width_inc * width_inc;
+            height = base_height + (height - base_height) / height_inc * height_inc;
+        }
+
+        (width.max(min_width).max(1), height.max(min_height).max(1))
+    }
+}
+
+/// A window's pixels, locked separately from `WindowManagerState` so that drawing from JS
+/// and presenting on the event loop do not contend on the shared metadata lock
+pub type SharedFramebuffer = Arc<Mutex<Framebuffer>>;
+
+/// Pixel storage for a window
+///
+/// The buffer is sized in physical pixels (logical size times `scale_factor`) so that it
+/// maps 1:1 onto the window on HiDPI displays; the JS drawing API works in logical pixels.
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    /// Physical pixels per logical pixel
+    pub scale_factor: f64,
+    /// ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
+    pub pixels: Vec<u32>,
+}
+
+impl Framebuffer {
+    /// Create a framebuffer cleared to fully transparent (transparent windows) or opaque black
+    pub fn new(width: u32, height: u32, transparent: bool) -> Self {
+        let pixel_count = width as usize * height as usize;
+        let pixels = if transparent {
+            vec![0x00000000u32; pixel_count] // Fully transparent ARGB (A=0, R=0, G=0, B=0)
+        } else {
+            vec![0xFF000000u32; pixel_count] // Opaque black ARGB (A=255, R=0, G=0, B=0)
+        };
+
+        Self {
+            width,
+            height,
+            scale_factor: 1.0,
+            pixels,
+        }
+    }
+
+    /// Create a shareable framebuffer
+    pub fn shared(width: u32, height: u32, transparent: bool) -> SharedFramebuffer {
+        Arc::new(Mutex::new(Self::new(width, height, transparent)))
+    }
+
+    /// Overwrite a single pixel, ignoring coordinates outside the buffer
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u32) {
+        if x < self.width && y < self.height {
+            let index = (y * self.width + x) as usize;
+            self.pixels[index] = color;
+        }
+    }
+
+    /// Blend a color onto a single pixel, ignoring coordinates outside the buffer
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: u32, mode: BlendMode) {
+        if x < self.width && y < self.height {
+            let index = (y * self.width + x) as usize;
+            self.pixels[index] = blend::blend(color, self.pixels[index], mode);
+        }
+    }
+
+    /// Fill the physical pixels covered by a logical pixel
+    pub fn set_logical_pixel(&mut self, x: u32, y: u32, color: u32, mode: BlendMode) {
+        if self.scale_factor == 1.0 {
+            return self.blend_pixel(x, y, color, mode);
+        }
+
+        let scale = |v: u32| (v as f64 * self.scale_factor).round() as i64;
+        let (x0, y0) = (scale(x), scale(y));
+        let (x1, y1) = (
+            scale(x.saturating_add(1)).max(x0 + 1),
+            scale(y.saturating_add(1)).max(y0 + 1),
+        );
+        let clamp = |v: i64| v.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.blend_rect(
+            clamp(x0),
+            clamp(y0),
+            (x1 - x0) as u32,
+            (y1 - y0) as u32,
+            color,
+            mode,
+        );
+    }
+
+    /// Reallocate for a new scale factor, keeping the logical size and resampling the
+    /// current contents (nearest neighbour) so nothing drawn so far is lost
+    pub fn rescale(&mut self, logical_width: u32, logical_height: u32, scale_factor: f64) {
+        let width = ((logical_width as f64 * scale_factor).round() as u32).max(1);
+        let height = ((logical_height as f64 * scale_factor).round() as u32).max(1);
+        self.scale_factor = scale_factor;
+        if width == self.width && height == self.height {
+            return;
+        }
+        if self.pixels.is_empty() {
+            self.width = width;
+            self.height = height;
+            self.pixels = vec![0; width as usize * height as usize];
+            return;
+        }
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            let src_y = (y as u64 * self.height as u64 / height as u64) as u32;
+            for x in 0..width {
+                let src_x = (x as u64 * self.width as u64 / width as u64) as u32;
+                pixels.push(self.pixels[src_y as usize * self.width as usize + src_x as usize]);
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+    }
+
+    /// Resize the buffer for a new logical size at the current scale factor, keeping what
+    /// has been drawn in the top-left corner and filling new pixels with `background`
+    pub fn resize(&mut self, logical_width: u32, logical_height: u32, background: u32) {
+        let width = ((logical_width as f64 * self.scale_factor).round() as u32).max(1);
+        let height = ((logical_height as f64 * self.scale_factor).round() as u32).max(1);
+        if width == self.width && height == self.height {
+            return;
+        }
+
+        let mut pixels = vec![background; width as usize * height as usize];
+        let copy_width = width.min(self.width) as usize;
+        for y in 0..height.min(self.height) as usize {
+            let src = y * self.width as usize;
+            let dst = y * width as usize;
+            pixels[dst..dst + copy_width].copy_from_slice(&self.pixels[src..src + copy_width]);
+        }
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+    }
+
+    /// Fill the whole buffer with one color
+    pub fn fill(&mut self, color: u32) {
+        self.pixels.fill(color);
+    }
+
+    /// Fill a rectangle with one color, clipped to the buffer
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: u32) {
+        let x0 = x.clamp(0, self.width as i32) as u32;
+        let y0 = y.clamp(0, self.height as i32) as u32;
+        let x1 = (x as i64 + width as i64).clamp(0, self.width as i64) as u32;
+        let y1 = (y as i64 + height as i64).clamp(0, self.height as i64) as u32;
+
+        for row in y0..y1 {
+            let start = (row * self.width + x0) as usize;
+            let end = (row * self.width + x1) as usize;
+            self.pixels[start..end].fill(color);
+        }
+    }
+
+    /// Blend a color onto a rectangle, clipped to the buffer
+    pub fn blend_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: u32,
+        mode: BlendMode,
+    ) {
+        if mode == BlendMode::Source {
+            return self.fill_rect(x, y, width, height, color);
+        }
+        let x0 = x.clamp(0, self.width as i32) as u32;
+        let y0 = y.clamp(0, self.height as i32) as u32;
+        let x1 = (x as i64 + width as i64).clamp(0, self.width as i64) as u32;
+        let y1 = (y as i64 + height as i64).clamp(0, self.height as i64) as u32;
+
+        for row in y0..y1 {
+            let start = (row * self.width + x0) as usize;
+            let end = (row * self.width + x1) as usize;
+            for pixel in &mut self.pixels[start..end] {
+                *pixel = blend::blend(color, *pixel, mode);
+            }
+        }
+    }
+}
+
+/// Number of recent frames kept for frame time averages and percentiles
+pub const FRAME_STATS_SAMPLES: usize = 240;
+
+/// Presentation statistics collected for each window
+#[derive(Default)]
+pub struct FrameStats {
+    /// Frames drawn for a `present`; redraws the window system asks for are not counted
+    pub presented_frames: u64,
+    /// `present` calls that were merged into a frame already waiting to be drawn
+    pub coalesced_presents: u64,
+    /// Set by `present` and cleared once the frame has been drawn
+    pub present_pending: bool,
+    /// Bytes sent to the X server by every drawn frame
+    pub bytes_uploaded: u64,
+    /// Time spent converting the pixel buffer in `render_window`, for every drawn frame
+    pub convert_times: VecDeque<Duration>,
+    /// Time spent uploading to the X server in `present_to_window`, for every drawn frame
+    pub upload_times: VecDeque<Duration>,
+}
+
+impl FrameStats {
+    /// Note a `present` call, counting it as coalesced if a frame is already waiting
+    pub fn request_present(&mut self) {
+        if self.present_pending {
+            self.coalesced_presents += 1;
+        }
+        self.present_pending = true;
+    }
+
+    /// Record one drawn frame, counting it as presented if a `present` was waiting for it
+    pub fn record_frame(&mut self, convert_time: Duration, upload_time: Duration, bytes: usize) {
+        if self.present_pending {
+            self.presented_frames += 1;
+        }
+        self.present_pending = false;
+        self.bytes_uploaded += bytes as u64;
+
+        for (samples, time) in [
+            (&mut self.convert_times, convert_time),
+            (&mut self.upload_times, upload_time),
+        ] {
+            if samples.len() == FRAME_STATS_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(time);
+        }
+    }
+}
+
+/// Average and 99th percentile of a set of frame times, in milliseconds
+pub fn frame_time_summary(samples: &VecDeque<Duration>) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+    sorted.sort_unstable();
+    let total: Duration = sorted.iter().sum();
+    let average = total.as_secs_f64() * 1000.0 / sorted.len() as f64;
+    let p99_index = ((sorted.len() as f64 * 0.99).ceil() as usize).clamp(1, sorted.len()) - 1;
+    let p99 = sorted[p99_index].as_secs_f64() * 1000.0;
+
+    (average, p99)
+}
+
+/// Frame statistics for a window, as returned to JS by `getStats`
+///
+/// There is no separate dropped-present count: a `present` is only ever dropped by being
+/// merged into a frame that is already waiting, which is what `coalescedPresents` counts.
+/// Timings and bytes cover every drawn frame, including redraws after expose and resize.
+#[napi(object)]
+pub struct WindowStats {
+    /// Frames drawn for a `present`
+    pub presented_frames: i64,
+    /// `present` calls merged into a frame that was already waiting to be drawn
+    pub coalesced_presents: i64,
+    pub bytes_uploaded: i64,
+    pub avg_convert_ms: f64,
+    pub p99_convert_ms: f64,
+    pub avg_upload_ms: f64,
+    pub p99_upload_ms: f64,
+    /// Number of recent frames the averages and percentiles are computed over
+    pub sample_count: u32,
+}
+
+/// A rectangle in window pixel coordinates, used for window shape regions
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShapeRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A monitor as reported by `getMonitors`; positions and sizes are in physical pixels
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    /// Index accepted by the methods that take a monitor
+    pub index: u32,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    /// Refresh rate in Hz, if known
+    pub refresh_rate: Option<f64>,
+    pub primary: bool,
+    /// The part of the monitor not covered by panels and docks
+    pub work_area: MonitorRect,
+}
+
+/// A rectangle in physical screen pixels
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonitorRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Where `placeOnMonitor` puts a window within a monitor's work area
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonitorAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl MonitorAnchor {
+    /// Fraction of the free space placed before the window, horizontally and vertically
+    pub fn alignment(self) -> (f64, f64) {
+        match self {
+            MonitorAnchor::TopLeft => (0.0, 0.0),
+            MonitorAnchor::Top => (0.5, 0.0),
+            MonitorAnchor::TopRight => (1.0, 0.0),
+            MonitorAnchor::Left => (0.0, 0.5),
+            MonitorAnchor::Center => (0.5, 0.5),
+            MonitorAnchor::Right => (1.0, 0.5),
+            MonitorAnchor::BottomLeft => (0.0, 1.0),
+            MonitorAnchor::Bottom => (0.5, 1.0),
+            MonitorAnchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// How colors passed to the pixel APIs are encoded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// Color channels are independent of alpha, e.g. half-transparent white is 0x80FFFFFF
+    #[default]
+    Straight,
+    /// Color channels are already multiplied by alpha, e.g. half-transparent white is 0x80808080
+    Premultiplied,
+}
+
+impl ColorFormat {
+    /// Convert a color in this format to the straight ARGB stored in framebuffers
+    pub fn to_straight(self, color: u32) -> u32 {
+        match self {
+            ColorFormat::Straight => color,
+            ColorFormat::Premultiplied => blend::unpremultiply(color),
+        }
+    }
+}
+
+/// Fullscreen modes for `setFullscreen`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A borderless window covering the monitor, keeping the current video mode
+    Borderless,
+    /// Switch the monitor to its largest video mode
+    Exclusive,
+}
+
+/// Window types for the `windowType` hint, mapped to `_NET_WM_WINDOW_TYPE` on X11
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowType {
+    #[default]
+    Normal,
+    Dialog,
+    Utility,
+    Toolbar,
+    Dock,
+    Splash,
+    Notification,
+    Tooltip,
+    PopupMenu,
+    Desktop,
+}
+
+/// X11 selections the clipboard API can read and write
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The selection used by copy and paste
+    #[default]
+    Clipboard,
+    /// The selection set by selecting text and pasted with the middle mouse button
+    Primary,
+}
+
+/// An image read from the clipboard
+#[napi(object)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    /// Straight (non-premultiplied) RGBA bytes, `width * height * 4` of them
+    pub data: Uint8Array,
+}
+
+/// Options for `createWindow` and `createWindowWithOptions`; every field is optional
+#[napi(object)]
+#[derive(Default)]
+pub struct WindowOptions {
+    /// Outer position in logical pixels; the window manager places the window when unset
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub always_on_top: Option<bool>,
+    /// Give the window an alpha channel; defaults to false
+    pub transparent: Option<bool>,
+    /// Show the title bar and borders; defaults to true
+    pub decorations: Option<bool>,
+    /// "straight" (the default) or "premultiplied", see `setColorFormat`
+    pub color_format: Option<String>,
+    /// "normal", "dialog", "utility", "toolbar", "dock", "splash", "notification",
+    /// "tooltip", "popup-menu" or "desktop"; defaults to "normal"
+    pub window_type: Option<String>,
+    /// Keep the window out of the taskbar
+    pub skip_taskbar: Option<bool>,
+    /// Keep the window out of pagers and workspace switchers
+    pub skip_pager: Option<bool>,
+    /// Id of the window this one belongs to (`WM_TRANSIENT_FOR`); the child stays above its
+    /// parent and closes with it
+    pub parent: Option<u32>,
+    /// Mark the child as modal for its parent; needs `parent`
+    pub modal: Option<bool>,
+    /// While this modal child is open, the parent gets no pointer input and focus given
+    /// to it moves to the child
+    pub block_parent_input: Option<bool>,
+    /// Application id, the instance part of `WM_CLASS`; defaults to `className`
+    pub app_id: Option<String>,
+    /// Class name taskbars group windows by, the class part of `WM_CLASS`; defaults to
+    /// `appId`
+    pub class_name: Option<String>,
+    /// Smallest size the user can resize the window to
+    pub min_size: Option<WindowSize>,
+    /// Largest size the user can resize the window to
+    pub max_size: Option<WindowSize>,
+    /// Steps the window is resized in, e.g. the size of a character cell
+    pub resize_increments: Option<WindowSize>,
+    /// Lock the window to this width to height ratio, e.g. 16 x 9
+    pub aspect_ratio: Option<WindowSize>,
+}
+
+/// Window manager hints from `WindowOptions` after validation, applied when the event loop
+/// creates the window
+#[derive(Clone, Debug, Default)]
+pub struct CreateHints {
+    pub window_type: WindowType,
+    pub skip_taskbar: bool,
+    pub skip_pager: bool,
+    pub parent: Option<u64>,
+    pub modal: bool,
+    pub block_parent_input: bool,
+    /// `(instance, class)` for `WM_CLASS`
+    pub wm_class: Option<(String, String)>,
+    pub size_constraints: SizeConstraints,
+}
+
 /// Commands that can be sent to the window manager
 pub enum WindowCommand {
     CreateWindow {
@@ -39,6 +735,7 @@ pub enum WindowCommand {
         always_on_top: bool,
         transparent: bool,
         decorations: bool,
+        hints: CreateHints,
     },
     SetPixel {
         window_id: u64,
@@ -70,14 +767,374 @@ pub enum WindowCommand {
         window_id: u64,
         ignore: bool,
     },
+    SetCursorIcon {
+        window_id: u64,
+        cursor: CursorIcon,
+    },
+    SetCursorVisible {
+        window_id: u64,
+        visible: bool,
+    },
+    SetCursorGrab {
+        window_id: u64,
+        mode: CursorGrabMode,
+    },
+    SetCustomCursor {
+        window_id: u64,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    },
+    SetShape {
+        window_id: u64,
+        rects: Option<Vec<ShapeRect>>,
+    },
+    SetAlphaHitTest {
+        window_id: u64,
+        threshold: Option<u8>,
+    },
+    Submit {
+        window_id: u64,
+        ops: Vec<DrawOp>,
+    },
+    SetMinimized {
+        window_id: u64,
+        minimized: bool,
+    },
+    SetMaximized {
+        window_id: u64,
+        maximized: bool,
+    },
+    /// `mode: None` leaves fullscreen; `monitor` indexes the available monitors and
+    /// defaults to the one the window is on
+    SetFullscreen {
+        window_id: u64,
+        mode: Option<FullscreenMode>,
+        monitor: Option<u32>,
+    },
+    SetVisible {
+        window_id: u64,
+        visible: bool,
+    },
+    Focus {
+        window_id: u64,
+    },
+    RequestUserAttention {
+        window_id: u64,
+        attention: Option<UserAttentionType>,
+    },
+    SetResizable {
+        window_id: u64,
+        resizable: bool,
+    },
+    SetDecorations {
+        window_id: u64,
+        decorations: bool,
+    },
+    /// Move a window to an anchor in a monitor's work area; offsets are logical pixels
+    /// towards the inside of the work area
+    PlaceOnMonitor {
+        window_id: u64,
+        monitor: Option<u32>,
+        anchor: MonitorAnchor,
+        offset_x: i32,
+        offset_y: i32,
+    },
+    SetOpacity {
+        window_id: u64,
+        opacity: f64,
+    },
+    SetSkipTaskbar {
+        window_id: u64,
+        skip: bool,
+    },
+    SetSkipPager {
+        window_id: u64,
+        skip: bool,
+    },
+    SetIcon {
+        window_id: u64,
+        icon: Icon,
+    },
+    /// Apply a window's `SizeConstraints` after one of them changed
+    SetSizeConstraints {
+        window_id: u64,
+        constraints: SizeConstraints,
+    },
+    /// Animate opacity from its current value to `to`, optionally closing the window at
+    /// the end
+    FadeOpacity {
+        window_id: u64,
+        to: f64,
+        duration: Duration,
+        close_when_done: bool,
+    },
     CloseWindow {
         window_id: u64,
     },
 }
 
+/// Duration of `fadeIn` and `fadeOut` when none is given
+pub const DEFAULT_FADE_MS: u32 = 200;
+
+/// An opacity animation run by the event loop
+#[derive(Clone, Copy, Debug)]
+pub struct OpacityFade {
+    pub from: f64,
+    pub to: f64,
+    pub started_at: Instant,
+    pub duration: Duration,
+    pub close_when_done: bool,
+}
+
+impl OpacityFade {
+    /// Opacity at a point in time, easing linearly from `from` to `to`
+    pub fn opacity_at(&self, now: Instant) -> f64 {
+        if self.is_done(now) {
+            return self.to;
+        }
+        let progress =
+            now.duration_since(self.started_at).as_secs_f64() / self.duration.as_secs_f64();
+        self.from + (self.to - self.from) * progress
+    }
+
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.duration_since(self.started_at) >= self.duration
+    }
+}
+
 /// Internal window data managed by the event loop
 pub struct ManagedWindow {
     pub window: Arc<winit::window::Window>,
     pub pixmap: tiny_skia::Pixmap,
     pub state_id: u64,
+    /// When animation frame callbacks last fired for this window
+    pub last_frame_at: Option<Instant>,
+    /// Opacity animation in progress, if any
+    pub fade: Option<OpacityFade>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logical_pixels_at_the_coordinate_limit_are_ignored() {
+        let mut framebuffer = Framebuffer::new(2, 2, true);
+        framebuffer.rescale(1, 1, 2.0);
+        framebuffer.set_logical_pixel(u32::MAX, u32::MAX, 0xFFFFFFFF, BlendMode::Source);
+        framebuffer.set_logical_pixel(0, u32::MAX, 0xFFFFFFFF, BlendMode::Source);
+
+        assert!(framebuffer.pixels.iter().all(|&pixel| pixel == 0));
+    }
+
+    fn window_state(parent: Option<u64>, blocks_parent_input: bool) -> WindowState {
+        WindowState {
+            width: 4,
+            height: 4,
+            framebuffer: Framebuffer::shared(4, 4, false),
+            needs_redraw: false,
+            title: String::new(),
+            x: None,
+            y: None,
+            always_on_top: false,
+            transparent: false,
+            supports_transparency: false,
+            decorations: true,
+            alpha_hit_threshold: None,
+            ignore_input: false,
+            color_format: ColorFormat::default(),
+            opacity: 1.0,
+            parent,
+            blocks_parent_input,
+            size_constraints: SizeConstraints::default(),
+            stats: FrameStats::default(),
+            native: NativeWindowState::requested(4, 4),
+            winit_id: None,
+        }
+    }
+
+    fn manager_state(windows: impl IntoIterator<Item = (u64, WindowState)>) -> WindowManagerState {
+        WindowManagerState {
+            windows: windows.into_iter().collect(),
+            frame_requests: Vec::new(),
+            next_frame_request_id: 0,
+            should_exit: false,
+            monitors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn ignoring_input_outlasts_a_blocking_modal_child() {
+        let mut parent = window_state(None, false);
+        parent.ignore_input = true;
+        let mut state = manager_state([(1, parent), (2, window_state(Some(1), true))]);
+        assert_eq!(state.input_region(1), Some(InputRegion::Empty));
+
+        state.windows.remove(&2);
+        assert_eq!(state.input_region(1), Some(InputRegion::Empty));
+
+        state.windows.get_mut(&1).unwrap().ignore_input = false;
+        assert_eq!(state.input_region(1), Some(InputRegion::Whole));
+        assert_eq!(state.input_region(3), None);
+    }
+
+    #[test]
+    fn ignoring_input_wins_over_alpha_hit_testing() {
+        let mut window = window_state(None, false);
+        window.alpha_hit_threshold = Some(128);
+        let mut state = manager_state([(1, window)]);
+        assert_eq!(state.input_region(1), Some(InputRegion::Alpha(128)));
+
+        let window = state.windows.get_mut(&1).unwrap();
+        window.ignore_input = true;
+        assert_eq!(state.input_region(1), Some(InputRegion::Empty));
+
+        let window = state.windows.get_mut(&1).unwrap();
+        window.alpha_hit_threshold = None;
+        assert_eq!(state.input_region(1), Some(InputRegion::Empty));
+    }
+
+    #[test]
+    fn clamp_raises_sizes_below_the_minimum() {
+        let constraints = SizeConstraints {
+            min_size: Some((100, 80)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.clamp(50, 200), (100, 200));
+        assert_eq!(constraints.clamp(0, 0), (100, 80));
+    }
+
+    #[test]
+    fn clamp_lowers_sizes_above_the_maximum() {
+        let constraints = SizeConstraints {
+            max_size: Some((200, 150)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.clamp(300, 100), (200, 100));
+        assert_eq!(constraints.clamp(300, 300), (200, 150));
+    }
+
+    #[test]
+    fn clamp_shrinks_the_longer_side_to_the_aspect_ratio() {
+        let constraints = SizeConstraints {
+            aspect_ratio: Some((2, 1)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.clamp(300, 300), (300, 150));
+        assert_eq!(constraints.clamp(500, 100), (200, 100));
+    }
+
+    #[test]
+    fn clamp_grows_instead_of_shrinking_below_the_minimum_for_the_aspect_ratio() {
+        let constraints = SizeConstraints {
+            min_size: Some((100, 100)),
+            aspect_ratio: Some((2, 1)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.clamp(150, 150), (200, 100));
+        assert_eq!(constraints.clamp(400, 300), (400, 200));
+    }
+
+    #[test]
+    fn clamp_counts_resize_increments_from_the_minimum() {
+        let constraints = SizeConstraints {
+            min_size: Some((100, 50)),
+            resize_increments: Some((10, 20)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.clamp(125, 95), (120, 90));
+        assert_eq!(constraints.clamp(100, 50), (100, 50));
+    }
+
+    #[test]
+    fn clamp_applies_every_constraint_together() {
+        let constraints = SizeConstraints {
+            min_size: Some((100, 100)),
+            max_size: Some((400, 300)),
+            resize_increments: Some((10, 10)),
+            aspect_ratio: Some((4, 3)),
+        };
+        assert_eq!(constraints.clamp(1000, 1000), (400, 300));
+        assert_eq!(constraints.clamp(260, 1000), (260, 190));
+        // Growing to keep the ratio at the minimum, then snapping to the increments
+        assert_eq!(constraints.clamp(50, 50), (130, 100));
+    }
+
+    fn millis(samples: impl IntoIterator<Item = u64>) -> VecDeque<Duration> {
+        samples.into_iter().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn frame_time_p99_is_the_sample_at_the_99th_percentile() {
+        assert_eq!(frame_time_summary(&millis([])), (0.0, 0.0));
+        assert_eq!(frame_time_summary(&millis([7])), (7.0, 7.0));
+        assert_eq!(frame_time_summary(&millis((1..=100).rev())).1, 99.0);
+        assert_eq!(frame_time_summary(&millis(1..=101)).1, 100.0);
+    }
+
+    #[test]
+    fn frame_stats_keep_only_the_latest_samples() {
+        let mut stats = FrameStats::default();
+        for i in 0..FRAME_STATS_SAMPLES as u64 + 10 {
+            stats.record_frame(Duration::from_millis(i), Duration::from_millis(i), 4);
+        }
+
+        for samples in [&stats.convert_times, &stats.upload_times] {
+            assert_eq!(samples.len(), FRAME_STATS_SAMPLES);
+            assert_eq!(samples.front(), Some(&Duration::from_millis(10)));
+        }
+        assert_eq!(stats.bytes_uploaded, 4 * (FRAME_STATS_SAMPLES as u64 + 10));
+    }
+
+    #[test]
+    fn presents_are_coalesced_only_while_one_is_pending() {
+        let mut stats = FrameStats::default();
+        stats.request_present();
+        stats.request_present();
+        stats.request_present();
+        assert_eq!(stats.coalesced_presents, 2);
+
+        stats.record_frame(Duration::ZERO, Duration::ZERO, 0);
+        stats.request_present();
+        assert_eq!(stats.coalesced_presents, 2);
+        assert_eq!(stats.presented_frames, 1);
+
+        stats.record_frame(Duration::ZERO, Duration::ZERO, 0);
+        // A redraw nobody presented, e.g. after an expose
+        stats.record_frame(Duration::ZERO, Duration::ZERO, 0);
+        assert_eq!(stats.presented_frames, 2);
+    }
+
+    fn fade(from: f64, to: f64, duration_ms: u64) -> (OpacityFade, Instant) {
+        let started_at = Instant::now();
+        let fade = OpacityFade {
+            from,
+            to,
+            started_at,
+            duration: Duration::from_millis(duration_ms),
+            close_when_done: false,
+        };
+        (fade, started_at)
+    }
+
+    #[test]
+    fn zero_duration_fade_is_done_at_once() {
+        let (fade, start) = fade(0.0, 1.0, 0);
+        assert!(fade.is_done(start));
+        assert_eq!(fade.opacity_at(start), 1.0);
+    }
+
+    #[test]
+    fn fade_eases_linearly_to_its_end_value() {
+        let (fade, start) = fade(1.0, 0.2, 200);
+        assert!(!fade.is_done(start));
+        assert_eq!(fade.opacity_at(start), 1.0);
+        assert!((fade.opacity_at(start + Duration::from_millis(100)) - 0.6).abs() < 1e-9);
+        assert!(fade.is_done(start + Duration::from_millis(200)));
+        assert_eq!(fade.opacity_at(start + Duration::from_millis(200)), 0.2);
+        assert_eq!(fade.opacity_at(start + Duration::from_secs(5)), 0.2);
+    }
 }
@@ -1,8 +1,252 @@
+use crate::renderer::window_manager::blend::BLEND_MODES;
+use crate::renderer::window_manager::types::{
+    ColorFormat, CreateHints, FullscreenMode, MonitorAnchor, Selection, SizeConstraints,
+    WindowOptions, WindowSize, WindowType,
+};
 use napi::bindgen_prelude::*;
 use napi::JsNumber;
+use tiny_skia::BlendMode;
+use winit::window::{CursorGrabMode, CursorIcon, Icon, UserAttentionType};
 
-/// Convert a JavaScript number to u64
-pub fn js_number_to_u64(n: JsNumber) -> Result<u64> {
-    let val = n.get_double()? as u64;
-    Ok(val)
+/// Validate a window id passed from JS
+/// Ids are positive integers that fit in a u32; NaN, negatives and fractions are rejected
+/// rather than truncated to some other window's id
+pub fn parse_window_id(n: JsNumber) -> Result<u64> {
+    let value = n.get_double()?;
+    if value.fract() != 0.0 || value < 1.0 || value > u32::MAX as f64 {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Invalid window id: {}", value),
+        ));
+    }
+    Ok(value as u64)
+}
+
+/// Parse a CSS-style cursor name (e.g. "pointer", "text", "crosshair") into a winit cursor
+pub fn parse_cursor_icon(name: &str) -> Result<CursorIcon> {
+    name.parse::<CursorIcon>().map_err(|_| {
+        napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown cursor name: {}", name),
+        )
+    })
+}
+
+/// Parse a cursor grab mode: "none", "confined" or "locked"
+pub fn parse_cursor_grab_mode(mode: &str) -> Result<CursorGrabMode> {
+    match mode {
+        "none" => Ok(CursorGrabMode::None),
+        "confined" => Ok(CursorGrabMode::Confined),
+        "locked" => Ok(CursorGrabMode::Locked),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown cursor grab mode: {}", mode),
+        )),
+    }
+}
+
+/// Parse a fullscreen mode: "borderless" or "exclusive"
+pub fn parse_fullscreen_mode(mode: &str) -> Result<FullscreenMode> {
+    match mode {
+        "borderless" => Ok(FullscreenMode::Borderless),
+        "exclusive" => Ok(FullscreenMode::Exclusive),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown fullscreen mode: {}", mode),
+        )),
+    }
+}
+
+/// Parse a user attention request: "critical", "informational", or "none" to cancel it
+pub fn parse_user_attention(attention: &str) -> Result<Option<UserAttentionType>> {
+    match attention {
+        "critical" => Ok(Some(UserAttentionType::Critical)),
+        "informational" => Ok(Some(UserAttentionType::Informational)),
+        "none" => Ok(None),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown user attention type: {}", attention),
+        )),
+    }
+}
+
+/// Parse a monitor anchor such as "center", "top-left" or "bottom"
+pub fn parse_monitor_anchor(anchor: &str) -> Result<MonitorAnchor> {
+    match anchor {
+        "top-left" => Ok(MonitorAnchor::TopLeft),
+        "top" => Ok(MonitorAnchor::Top),
+        "top-right" => Ok(MonitorAnchor::TopRight),
+        "left" => Ok(MonitorAnchor::Left),
+        "center" => Ok(MonitorAnchor::Center),
+        "right" => Ok(MonitorAnchor::Right),
+        "bottom-left" => Ok(MonitorAnchor::BottomLeft),
+        "bottom" => Ok(MonitorAnchor::Bottom),
+        "bottom-right" => Ok(MonitorAnchor::BottomRight),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown monitor anchor: {}", anchor),
+        )),
+    }
+}
+
+/// Parse a window type such as "dock", "notification" or "popup-menu"
+pub fn parse_window_type(window_type: &str) -> Result<WindowType> {
+    match window_type {
+        "normal" => Ok(WindowType::Normal),
+        "dialog" => Ok(WindowType::Dialog),
+        "utility" => Ok(WindowType::Utility),
+        "toolbar" => Ok(WindowType::Toolbar),
+        "dock" => Ok(WindowType::Dock),
+        "splash" => Ok(WindowType::Splash),
+        "notification" => Ok(WindowType::Notification),
+        "tooltip" => Ok(WindowType::Tooltip),
+        "popup-menu" => Ok(WindowType::PopupMenu),
+        "desktop" => Ok(WindowType::Desktop),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown window type: {}", window_type),
+        )),
+    }
+}
+
+/// Parse a clipboard selection: "clipboard" (the default) or "primary"
+pub fn parse_selection(selection: Option<&str>) -> Result<Selection> {
+    match selection {
+        None | Some("clipboard") => Ok(Selection::Clipboard),
+        Some("primary") => Ok(Selection::Primary),
+        Some(other) => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown selection: {}", other),
+        )),
+    }
+}
+
+/// Validate the window manager hints in the options of `createWindow`
+pub fn parse_window_hints(hints: WindowOptions) -> Result<CreateHints> {
+    let modal = hints.modal.unwrap_or(false);
+    if modal && hints.parent.is_none() {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            "A modal window needs a parent",
+        ));
+    }
+    Ok(CreateHints {
+        window_type: match hints.window_type {
+            Some(window_type) => parse_window_type(&window_type)?,
+            None => WindowType::Normal,
+        },
+        skip_taskbar: hints.skip_taskbar.unwrap_or(false),
+        skip_pager: hints.skip_pager.unwrap_or(false),
+        parent: hints.parent.map(u64::from),
+        modal,
+        block_parent_input: modal && hints.block_parent_input.unwrap_or(false),
+        wm_class: match (hints.app_id, hints.class_name) {
+            (Some(app_id), Some(class_name)) => Some((app_id, class_name)),
+            (Some(name), None) | (None, Some(name)) => Some((name.clone(), name)),
+            (None, None) => None,
+        },
+        size_constraints: check_size_constraints(SizeConstraints {
+            min_size: parse_size_limit("Minimum size", hints.min_size)?,
+            max_size: parse_size_limit("Maximum size", hints.max_size)?,
+            resize_increments: parse_size_limit("Resize increments", hints.resize_increments)?,
+            aspect_ratio: parse_size_limit("Aspect ratio", hints.aspect_ratio)?,
+        })?,
+    })
+}
+
+/// A size from separate width and height arguments, where null for both means no size
+pub fn parse_optional_size(width: Option<u32>, height: Option<u32>) -> Result<Option<WindowSize>> {
+    match (width, height) {
+        (Some(width), Some(height)) => Ok(Some(WindowSize { width, height })),
+        (None, None) => Ok(None),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            "Pass both width and height, or null for both",
+        )),
+    }
+}
+
+/// Validate one of a window's size limits; both sides must be greater than 0
+pub fn parse_size_limit(what: &str, size: Option<WindowSize>) -> Result<Option<(u32, u32)>> {
+    match size {
+        Some(size) if size.width == 0 || size.height == 0 => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!(
+                "{} must be greater than 0, got {}x{}",
+                what, size.width, size.height
+            ),
+        )),
+        Some(size) => Ok(Some((size.width, size.height))),
+        None => Ok(None),
+    }
+}
+
+/// Reject a minimum size larger than the maximum size
+pub fn check_size_constraints(constraints: SizeConstraints) -> Result<SizeConstraints> {
+    if let (Some(min), Some(max)) = (constraints.min_size, constraints.max_size) {
+        if min.0 > max.0 || min.1 > max.1 {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                format!(
+                    "Minimum size {}x{} is larger than maximum size {}x{}",
+                    min.0, min.1, max.0, max.1
+                ),
+            ));
+        }
+    }
+    Ok(constraints)
+}
+
+/// Build a window icon from straight RGBA bytes, `width * height * 4` of them
+pub fn parse_icon(rgba: &[u8], width: u32, height: u32) -> Result<Icon> {
+    if width == 0 || height == 0 {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            "Icon width and height must be greater than 0",
+        ));
+    }
+    Icon::from_rgba(rgba.to_vec(), width, height)
+        .map_err(|e| napi::Error::new(napi::Status::InvalidArg, format!("Invalid icon: {}", e)))
+}
+
+/// Validate a whole-window opacity, which must be between 0.0 and 1.0
+pub fn parse_opacity(opacity: f64) -> Result<f64> {
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Opacity must be between 0 and 1, got {}", opacity),
+        ));
+    }
+    Ok(opacity)
+}
+
+/// Parse a color format: "straight" or "premultiplied"
+pub fn parse_color_format(format: &str) -> Result<ColorFormat> {
+    match format {
+        "straight" => Ok(ColorFormat::Straight),
+        "premultiplied" => Ok(ColorFormat::Premultiplied),
+        _ => Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("Unknown color format: {}", format),
+        )),
+    }
+}
+
+/// Parse a blend mode such as "source-over", "multiply" or "screen"
+/// "additive" is accepted as another name for "plus"; `None` overwrites ("source")
+pub fn parse_blend_mode(mode: Option<&str>) -> Result<BlendMode> {
+    match mode {
+        None => Ok(BlendMode::Source),
+        Some("additive") => Ok(BlendMode::Plus),
+        Some(name) => BLEND_MODES
+            .iter()
+            .find(|&&(mode_name, _)| mode_name == name)
+            .map(|&(_, mode)| mode)
+            .ok_or_else(|| {
+                napi::Error::new(
+                    napi::Status::InvalidArg,
+                    format!("Unknown blend mode: {}", name),
+                )
+            }),
+    }
 }
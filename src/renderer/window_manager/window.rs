@@ -0,0 +1,343 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use crate::renderer::window_manager::command_queue::CommandSender;
+use crate::renderer::window_manager::types::*;
+use crate::renderer::window_manager::utils::{
+    parse_blend_mode, parse_color_format, parse_icon, parse_opacity,
+};
+
+/// A window created by `WindowManager`, with its own drawing and window methods
+///
+/// Only a weak reference to the manager's state is kept, so a `Window` left alive in JS
+/// does not keep the manager alive. Once the window has been closed (by `close`, by the
+/// user, or because the manager is gone) every method fails with a clear error.
+#[napi]
+pub struct Window {
+    id: u64,
+    state: Weak<Mutex<WindowManagerState>>,
+    commands: CommandSender,
+    closed: AtomicBool,
+}
+
+impl Window {
+    pub(crate) fn new(id: u64, state: &SharedState, commands: CommandSender) -> Self {
+        Self {
+            id,
+            state: Arc::downgrade(state),
+            commands,
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn closed_error(&self) -> napi::Error {
+        napi::Error::new(
+            napi::Status::GenericFailure,
+            format!("Window {} has been closed", self.id),
+        )
+    }
+
+    /// The manager's state, or an error if this window or the manager is gone
+    fn shared_state(&self) -> Result<SharedState> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(self.closed_error());
+        }
+        self.state.upgrade().ok_or_else(|| self.closed_error())
+    }
+
+    /// Run a closure against this window's state under the metadata lock
+    fn with_state<R>(&self, f: impl FnOnce(&mut WindowState) -> R) -> Result<R> {
+        let state = self.shared_state()?;
+        let mut state = state
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+
+        match state.windows.get_mut(&self.id) {
+            Some(window_state) => Ok(f(window_state)),
+            None => Err(self.closed_error()),
+        }
+    }
+
+    /// Write to the window's pixels; they reach the screen on the next `present` or `submit`
+    /// The writer gets the window's color format
+    fn write_framebuffer(&self, write: impl FnOnce(&mut Framebuffer, ColorFormat)) -> Result<()> {
+        let (framebuffer, color_format) = self.with_state(|window_state| {
+            (window_state.framebuffer.clone(), window_state.color_format)
+        })?;
+
+        let mut framebuffer = framebuffer
+            .lock()
+            .map_err(|_| napi::Error::new(napi::Status::GenericFailure, "Lock poisoned"))?;
+        write(&mut framebuffer, color_format);
+        Ok(())
+    }
+}
+
+#[napi]
+impl Window {
+    /// The window's id, accepted by the id-based `WindowManager` methods
+    #[napi(getter)]
+    pub fn id(&self) -> u32 {
+        self.id as u32
+    }
+
+    /// Whether the window is still open
+    #[napi(getter)]
+    pub fn is_open(&self) -> bool {
+        self.with_state(|_| ()).is_ok()
+    }
+
+    #[napi(getter)]
+    pub fn width(&self) -> Result<u32> {
+        self.with_state(|window_state| window_state.width)
+    }
+
+    #[napi(getter)]
+    pub fn height(&self) -> Result<u32> {
+        self.with_state(|window_state| window_state.height)
+    }
+
+    #[napi(getter)]
+    pub fn title(&self) -> Result<String> {
+        self.with_state(|window_state| window_state.title.clone())
+    }
+
+    /// Id of the window this one was created as a child of, if any
+    #[napi(getter)]
+    pub fn parent(&self) -> Result<Option<u32>> {
+        self.with_state(|window_state| window_state.parent.map(|parent| parent as u32))
+    }
+
+    /// Whether transparent pixels show what is behind the window, see
+    /// `WindowManager.supportsTransparency`
+    #[napi(getter)]
+    pub fn supports_transparency(&self) -> Result<bool> {
+        self.with_state(|window_state| window_state.supports_transparency)
+    }
+
+    /// The window's position, updated when the window system reports a move
+    #[napi(getter)]
+    pub fn x(&self) -> Result<Option<i32>> {
+        self.with_state(|window_state| window_state.x)
+    }
+
+    #[napi(getter)]
+    pub fn y(&self) -> Result<Option<i32>> {
+        self.with_state(|window_state| window_state.y)
+    }
+
+    /// Outer position in logical pixels as reported by the window system, if known
+    #[napi]
+    pub fn get_position(&self) -> Result<Option<WindowPosition>> {
+        self.with_state(|window_state| window_state.native.position())
+    }
+
+    /// Drawing size in logical pixels
+    #[napi]
+    pub fn get_size(&self) -> Result<WindowSize> {
+        self.with_state(|window_state| WindowSize {
+            width: window_state.width,
+            height: window_state.height,
+        })
+    }
+
+    /// Size of the client area in logical pixels
+    #[napi]
+    pub fn get_inner_size(&self) -> Result<WindowSize> {
+        self.with_state(|window_state| {
+            let native = &window_state.native;
+            native.logical_size(native.inner_size)
+        })
+    }
+
+    /// Size including decorations in logical pixels
+    #[napi]
+    pub fn get_outer_size(&self) -> Result<WindowSize> {
+        self.with_state(|window_state| {
+            let native = &window_state.native;
+            native.logical_size(native.outer_size)
+        })
+    }
+
+    #[napi]
+    pub fn is_focused(&self) -> Result<bool> {
+        self.with_state(|window_state| window_state.native.focused)
+    }
+
+    #[napi]
+    pub fn is_visible(&self) -> Result<bool> {
+        self.with_state(|window_state| window_state.native.visible)
+    }
+
+    #[napi]
+    pub fn is_minimized(&self) -> Result<bool> {
+        self.with_state(|window_state| window_state.native.minimized)
+    }
+
+    #[napi]
+    pub fn is_maximized(&self) -> Result<bool> {
+        self.with_state(|window_state| window_state.native.maximized)
+    }
+
+    #[napi]
+    pub fn get_scale_factor(&self) -> Result<f64> {
+        self.with_state(|window_state| window_state.native.scale_factor)
+    }
+
+    /// Set a pixel in the window's buffer; alpha defaults to 255 (fully opaque)
+    /// Without a `blendMode` the pixel is replaced; pass "source-over" to draw over it
+    #[napi]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_pixel(
+        &self,
+        x: u32,
+        y: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: Option<u8>,
+        blend_mode: Option<String>,
+    ) -> Result<()> {
+        let mode = parse_blend_mode(blend_mode.as_deref())?;
+        // ARGB format: AAAA AAAA RRRR RRRR GGGG GGGG BBBB BBBB
+        let alpha = a.unwrap_or(0xFF);
+        let color = ((alpha as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+
+        self.write_framebuffer(|framebuffer, format| {
+            framebuffer.set_logical_pixel(x, y, format.to_straight(color), mode)
+        })
+    }
+
+    /// Clear the window's buffer to a color; alpha defaults to 255 (fully opaque)
+    #[napi]
+    pub fn clear(&self, r: u8, g: u8, b: u8, a: Option<u8>) -> Result<()> {
+        let alpha = a.unwrap_or(0xFF);
+        let color = ((alpha as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+
+        self.write_framebuffer(|framebuffer, format| framebuffer.fill(format.to_straight(color)))
+    }
+
+    /// Set how colors passed to this window's pixel methods are encoded: "straight" (the
+    /// default) or "premultiplied"
+    #[napi]
+    pub fn set_color_format(&self, format: String) -> Result<()> {
+        let format = parse_color_format(&format)?;
+        self.with_state(|window_state| window_state.color_format = format)
+    }
+
+    /// Present the window's buffer; pixel writes stay off screen until the next present
+    #[napi]
+    pub fn present(&self) -> Result<()> {
+        self.with_state(|window_state| {
+            window_state.needs_redraw = true;
+            window_state.stats.request_present();
+        })?;
+
+        self.commands
+            .send(WindowCommand::Present { window_id: self.id });
+        Ok(())
+    }
+
+    #[napi]
+    pub fn set_title(&self, title: String) -> Result<()> {
+        self.with_state(|window_state| window_state.title = title.clone())?;
+
+        self.commands.send(WindowCommand::SetTitle {
+            window_id: self.id,
+            title,
+        });
+        Ok(())
+    }
+
+    #[napi]
+    pub fn set_position(&self, x: i32, y: i32) -> Result<()> {
+        self.with_state(|window_state| {
+            window_state.x = Some(x);
+            window_state.y = Some(y);
+        })?;
+
+        self.commands.send(WindowCommand::SetPosition {
+            window_id: self.id,
+            x,
+            y,
+        });
+        Ok(())
+    }
+
+    /// Set whole-window opacity from 0.0 to 1.0, see `WindowManager.setOpacity`
+    #[napi]
+    pub fn set_opacity(&self, opacity: f64) -> Result<()> {
+        let opacity = parse_opacity(opacity)?;
+        self.with_state(|window_state| window_state.opacity = opacity)?;
+
+        self.commands.send(WindowCommand::SetOpacity {
+            window_id: self.id,
+            opacity,
+        });
+        Ok(())
+    }
+
+    /// Set the window icon from straight RGBA bytes, see `WindowManager.setIcon`
+    #[napi]
+    pub fn set_icon(&self, rgba: Uint8Array, width: u32, height: u32) -> Result<()> {
+        let icon = parse_icon(&rgba, width, height)?;
+        self.with_state(|_| ())?;
+
+        self.commands.send(WindowCommand::SetIcon {
+            window_id: self.id,
+            icon,
+        });
+        Ok(())
+    }
+
+    #[napi]
+    pub fn get_opacity(&self) -> Result<f64> {
+        self.with_state(|window_state| window_state.opacity)
+    }
+
+    /// Fade in from fully transparent; `durationMs` defaults to 200
+    #[napi]
+    pub fn fade_in(&self, duration_ms: Option<u32>) -> Result<()> {
+        self.with_state(|window_state| window_state.opacity = 0.0)?;
+
+        self.commands.send(WindowCommand::SetOpacity {
+            window_id: self.id,
+            opacity: 0.0,
+        });
+        self.commands.send(WindowCommand::FadeOpacity {
+            window_id: self.id,
+            to: 1.0,
+            duration: Duration::from_millis(duration_ms.unwrap_or(DEFAULT_FADE_MS) as u64),
+            close_when_done: false,
+        });
+        Ok(())
+    }
+
+    /// Fade out to fully transparent, closing the window at the end with `closeWhenDone`
+    #[napi]
+    pub fn fade_out(&self, duration_ms: Option<u32>, close_when_done: Option<bool>) -> Result<()> {
+        self.with_state(|_| ())?;
+
+        self.commands.send(WindowCommand::FadeOpacity {
+            window_id: self.id,
+            to: 0.0,
+            duration: Duration::from_millis(duration_ms.unwrap_or(DEFAULT_FADE_MS) as u64),
+            close_when_done: close_when_done.unwrap_or(false),
+        });
+        Ok(())
+    }
+
+    /// Close the window; further calls on this object fail
+    #[napi]
+    pub fn close(&self) -> Result<()> {
+        self.with_state(|_| ())?;
+        self.closed.store(true, Ordering::Release);
+
+        self.commands
+            .send(WindowCommand::CloseWindow { window_id: self.id });
+        Ok(())
+    }
+}
@@ -0,0 +1,933 @@
+//! X11 helpers for window features that winit 0.29 does not expose directly
+
+use crate::renderer::window_manager::types::ShapeRect;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_long, c_ulong};
+use std::sync::Arc;
+
+/// How a visual lays out pixels in a ZPixmap image
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VisualFormat {
+    pub depth: u32,
+    /// Bits used by one pixel in memory, from the server's pixmap formats (e.g. 32 for most
+    /// depth-24 visuals)
+    pub bits_per_pixel: u32,
+    /// Rows are padded to a multiple of this many bits
+    pub scanline_pad: u32,
+    pub red_mask: c_ulong,
+    pub green_mask: c_ulong,
+    pub blue_mask: c_ulong,
+    /// Whether the server expects multi-byte pixels most significant byte first
+    pub big_endian: bool,
+}
+
+impl VisualFormat {
+    /// Describe a visual of the given depth on a display
+    ///
+    /// # Safety
+    /// `display` must be an open display and `visual` null or a visual of that display
+    #[cfg(target_os = "linux")]
+    pub unsafe fn query(
+        display: *mut x11::xlib::Display,
+        visual: *const x11::xlib::Visual,
+        depth: u32,
+    ) -> Option<Self> {
+        use std::os::raw::c_int;
+        use x11::xlib;
+
+        if visual.is_null() {
+            return None;
+        }
+
+        let mut count: c_int = 0;
+        let formats = xlib::XListPixmapFormats(display, &mut count);
+        if formats.is_null() {
+            return None;
+        }
+        let pixmap_format = std::slice::from_raw_parts(formats, count as usize)
+            .iter()
+            .find(|format| format.depth as u32 == depth)
+            .copied();
+        xlib::XFree(formats as *mut _);
+        let pixmap_format = pixmap_format?;
+
+        Some(Self {
+            depth,
+            bits_per_pixel: pixmap_format.bits_per_pixel as u32,
+            scanline_pad: pixmap_format.scanline_pad as u32,
+            red_mask: (*visual).red_mask,
+            green_mask: (*visual).green_mask,
+            blue_mask: (*visual).blue_mask,
+            big_endian: xlib::XImageByteOrder(display) == xlib::MSBFirst,
+        })
+    }
+
+    /// Bits of the pixel not used by red, green or blue; these carry alpha on 32-bit visuals
+    fn alpha_mask(&self) -> c_ulong {
+        let depth_mask = if self.depth >= c_ulong::BITS {
+            c_ulong::MAX
+        } else {
+            (1 << self.depth) - 1
+        };
+        depth_mask & !(self.red_mask | self.green_mask | self.blue_mask)
+    }
+
+    /// Bytes in one row of an image `width` pixels wide, including padding
+    pub fn bytes_per_line(&self, width: u32) -> usize {
+        let pad = self.scanline_pad.max(8) as usize;
+        let bits = width as usize * self.bits_per_pixel as usize;
+        bits.div_ceil(pad) * pad / 8
+    }
+}
+
+/// Scale an 8-bit channel into the bits of a mask
+fn pack_channel(value: u8, mask: c_ulong) -> c_ulong {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = mask >> shift;
+    ((value as c_ulong * max + 127) / 255) << shift & mask
+}
+
+/// Convert premultiplied RGBA pixmap data to the bytes of a ZPixmap image for a visual,
+/// returning the data and its bytes per line
+///
+/// Pixels are packed by the visual's channel masks into `bits_per_pixel` and written in the
+/// server's byte order. Any bits of a 32-bit visual outside the color masks get alpha, which
+/// compositors expect premultiplied. Visuals without alpha get the image drawn over black.
+/// Returns `None` for pixel sizes that are not a whole number of bytes.
+pub fn pixmap_to_visual(
+    rgba: &[u8],
+    width: u32,
+    format: &VisualFormat,
+) -> Option<(Vec<u8>, usize)> {
+    let bytes_per_pixel = match format.bits_per_pixel {
+        8 | 16 | 24 | 32 => format.bits_per_pixel as usize / 8,
+        _ => return None,
+    };
+    let bytes_per_line = format.bytes_per_line(width);
+    let row_bytes = width as usize * 4;
+    let height = rgba.len().checked_div(row_bytes).unwrap_or(0);
+    let alpha_mask = format.alpha_mask();
+
+    let mut data = vec![0u8; bytes_per_line * height];
+    for (src_row, dst_row) in rgba
+        .chunks_exact(row_bytes.max(1))
+        .zip(data.chunks_exact_mut(bytes_per_line.max(1)))
+    {
+        for (chunk, dst) in src_row
+            .chunks_exact(4)
+            .zip(dst_row.chunks_exact_mut(bytes_per_pixel))
+        {
+            let pixel = pack_channel(chunk[0], format.red_mask)
+                | pack_channel(chunk[1], format.green_mask)
+                | pack_channel(chunk[2], format.blue_mask)
+                | pack_channel(chunk[3], alpha_mask);
+            let bytes = pixel.to_le_bytes();
+            for (i, byte) in dst.iter_mut().enumerate() {
+                let index = if format.big_endian {
+                    bytes_per_pixel - 1 - i
+                } else {
+                    i
+                };
+                *byte = bytes[index];
+            }
+        }
+    }
+
+    Some((data, bytes_per_line))
+}
+
+/// Run a closure against the Xlib display and window backing a winit window.
+///
+/// A short-lived display connection is opened for the call, mirroring how
+/// `present_to_window` talks to the server. Returns `None` if the window is
+/// not an Xlib window or the display cannot be opened.
+#[cfg(target_os = "linux")]
+pub fn with_xlib_window<R>(
+    window: &Arc<winit::window::Window>,
+    f: impl FnOnce(*mut x11::xlib::Display, x11::xlib::Window) -> R,
+) -> Option<R> {
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use x11::xlib;
+
+    let RawWindowHandle::Xlib(xlib_handle) = window.raw_window_handle() else {
+        return None;
+    };
+
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            eprintln!("Failed to open X11 display");
+            return None;
+        }
+
+        let result = f(display, xlib_handle.window as xlib::Window);
+
+        xlib::XFlush(display);
+        xlib::XCloseDisplay(display);
+        Some(result)
+    }
+}
+
+/// Set a custom cursor image on a window from straight-alpha RGBA pixels.
+///
+/// The cursor is built with XRender so it keeps full alpha. It stays defined on
+/// the window until winit sets a cursor again (e.g. via `set_cursor_icon`).
+pub fn set_custom_cursor(
+    window: &Arc<winit::window::Window>,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    hotspot_x: u32,
+    hotspot_y: u32,
+) {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::raw::c_uint;
+        use x11::{xlib, xrender};
+
+        // XRender cursors take premultiplied ARGB32 in the client's byte order
+        let mut argb: Vec<u32> = rgba
+            .chunks_exact(4)
+            .map(|px| {
+                let a = px[3] as u32;
+                let premultiply = |c: u8| (c as u32 * a + 127) / 255;
+                (a << 24)
+                    | (premultiply(px[0]) << 16)
+                    | (premultiply(px[1]) << 8)
+                    | premultiply(px[2])
+            })
+            .collect();
+
+        let result = with_xlib_window(window, |display, x_window| unsafe {
+            let root = xlib::XDefaultRootWindow(display);
+            let pixmap = xlib::XCreatePixmap(display, root, width, height, 32);
+
+            let ximage = xlib::XCreateImage(
+                display,
+                std::ptr::null_mut(),
+                32,
+                xlib::ZPixmap,
+                0,
+                argb.as_mut_ptr() as *mut i8,
+                width as c_uint,
+                height as c_uint,
+                32,
+                0,
+            );
+            if ximage.is_null() {
+                xlib::XFreePixmap(display, pixmap);
+                return Err("Failed to create XImage");
+            }
+
+            let gc = xlib::XCreateGC(display, pixmap, 0, std::ptr::null_mut());
+            xlib::XPutImage(display, pixmap, gc, ximage, 0, 0, 0, 0, width, height);
+            xlib::XFreeGC(display, gc);
+
+            // Set data to null before destroying to prevent X11 from freeing our data
+            (*ximage).data = std::ptr::null_mut();
+            xlib::XDestroyImage(ximage);
+
+            let format = xrender::XRenderFindStandardFormat(display, xrender::PictStandardARGB32);
+            if format.is_null() {
+                xlib::XFreePixmap(display, pixmap);
+                return Err("ARGB32 picture format not available");
+            }
+
+            let picture =
+                xrender::XRenderCreatePicture(display, pixmap, format, 0, std::ptr::null());
+            let cursor = xrender::XRenderCreateCursor(
+                display,
+                picture,
+                hotspot_x as c_uint,
+                hotspot_y as c_uint,
+            );
+            xlib::XDefineCursor(display, x_window, cursor);
+
+            // The window keeps the cursor alive after these handles are released
+            xlib::XFreeCursor(display, cursor);
+            xrender::XRenderFreePicture(display, picture);
+            xlib::XFreePixmap(display, pixmap);
+            Ok(())
+        });
+
+        if let Some(Err(e)) = result {
+            eprintln!("Failed to set custom cursor: {}", e);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, rgba, width, height, hotspot_x, hotspot_y);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// X Shape extension region kinds (from `X11/extensions/shape.h`)
+pub const SHAPE_BOUNDING: i32 = 0;
+pub const SHAPE_INPUT: i32 = 2;
+
+/// Build a set of rectangles covering every pixel whose alpha is at least `threshold`
+pub fn alpha_mask_rects(pixels: &[u32], width: u32, height: u32, threshold: u8) -> Vec<ShapeRect> {
+    mask_rects(width, height, |i| ((pixels[i] >> 24) as u8) >= threshold)
+}
+
+/// Build a set of rectangles covering every pixel for which `covered(index)` is true.
+///
+/// Runs of covered pixels are collected per row, and runs spanning the same
+/// columns on consecutive rows are merged so that solid areas stay cheap.
+pub fn mask_rects(width: u32, height: u32, covered: impl Fn(usize) -> bool) -> Vec<ShapeRect> {
+    let mut rects: Vec<ShapeRect> = Vec::new();
+    // Rects that ended on the previous row, keyed by (x, width), so they can grow downwards
+    let mut open: HashMap<(i32, u32), usize> = HashMap::new();
+
+    for y in 0..height {
+        let row_start = y as usize * width as usize;
+        let mut next_open = HashMap::new();
+        let mut x = 0u32;
+
+        while x < width {
+            if !covered(row_start + x as usize) {
+                x += 1;
+                continue;
+            }
+
+            let start = x;
+            while x < width && covered(row_start + x as usize) {
+                x += 1;
+            }
+
+            let key = (start as i32, x - start);
+            let index = match open.get(&key) {
+                Some(&index) => {
+                    rects[index].height += 1;
+                    index
+                }
+                None => {
+                    rects.push(ShapeRect {
+                        x: start as i32,
+                        y: y as i32,
+                        width: x - start,
+                        height: 1,
+                    });
+                    rects.len() - 1
+                }
+            };
+            next_open.insert(key, index);
+        }
+
+        open = next_open;
+    }
+
+    rects
+}
+
+/// Scale rectangles from logical to physical pixels, rounding edges outwards
+pub fn scale_rects(rects: &[ShapeRect], scale_factor: f64) -> Vec<ShapeRect> {
+    if scale_factor == 1.0 {
+        return rects.to_vec();
+    }
+
+    rects
+        .iter()
+        .map(|r| {
+            let x0 = (r.x as f64 * scale_factor).floor();
+            let y0 = (r.y as f64 * scale_factor).floor();
+            let x1 = ((r.x as f64 + r.width as f64) * scale_factor).ceil();
+            let y1 = ((r.y as f64 + r.height as f64) * scale_factor).ceil();
+            ShapeRect {
+                x: x0 as i32,
+                y: y0 as i32,
+                width: (x1 - x0) as u32,
+                height: (y1 - y0) as u32,
+            }
+        })
+        .collect()
+}
+
+/// Replace one of a window's shape regions (`SHAPE_BOUNDING` or `SHAPE_INPUT`).
+///
+/// Passing `None` resets the region to the default, i.e. the whole window.
+pub fn set_shape_region(
+    window: &Arc<winit::window::Window>,
+    kind: i32,
+    rects: Option<&[ShapeRect]>,
+) {
+    #[cfg(target_os = "linux")]
+    {
+        use x11::{xfixes, xlib};
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            match rects {
+                Some(rects) => {
+                    let mut xrects: Vec<xlib::XRectangle> = rects
+                        .iter()
+                        .map(|r| xlib::XRectangle {
+                            x: r.x.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                            y: r.y.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                            width: r.width.min(u16::MAX as u32) as u16,
+                            height: r.height.min(u16::MAX as u32) as u16,
+                        })
+                        .collect();
+                    let region = xfixes::XFixesCreateRegion(
+                        display,
+                        xrects.as_mut_ptr(),
+                        xrects.len() as i32,
+                    );
+                    xfixes::XFixesSetWindowShapeRegion(display, x_window, kind, 0, 0, region);
+                    xfixes::XFixesDestroyRegion(display, region);
+                }
+                None => {
+                    xfixes::XFixesSetWindowShapeRegion(display, x_window, kind, 0, 0, 0);
+                }
+            }
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, kind, rects);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// The desktop work area (the screen minus panels and docks) from `_NET_WORKAREA`,
+/// as (x, y, width, height) in physical pixels across all monitors
+pub fn work_area() -> Option<(i32, i32, u32, u32)> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::raw::{c_int, c_long, c_uchar, c_ulong};
+        use x11::xlib;
+
+        unsafe {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let root = xlib::XDefaultRootWindow(display);
+
+            // Reads a CARD32 array property from the root window
+            let read_cardinals = |name: &[u8]| -> Vec<c_long> {
+                let atom = xlib::XInternAtom(display, name.as_ptr() as *const i8, xlib::True);
+                if atom == 0 {
+                    return Vec::new();
+                }
+
+                let mut actual_type = 0;
+                let mut actual_format: c_int = 0;
+                let mut item_count: c_ulong = 0;
+                let mut bytes_after: c_ulong = 0;
+                let mut data: *mut c_uchar = std::ptr::null_mut();
+                let status = xlib::XGetWindowProperty(
+                    display,
+                    root,
+                    atom,
+                    0,
+                    1024,
+                    xlib::False,
+                    xlib::XA_CARDINAL,
+                    &mut actual_type,
+                    &mut actual_format,
+                    &mut item_count,
+                    &mut bytes_after,
+                    &mut data,
+                );
+                if status != 0 || data.is_null() {
+                    return Vec::new();
+                }
+
+                // Format 32 properties are returned as an array of C longs
+                let values = if actual_format == 32 {
+                    std::slice::from_raw_parts(data as *const c_long, item_count as usize).to_vec()
+                } else {
+                    Vec::new()
+                };
+                xlib::XFree(data as *mut _);
+                values
+            };
+
+            let desktop = read_cardinals(b"_NET_CURRENT_DESKTOP\0")
+                .first()
+                .copied()
+                .unwrap_or(0) as usize;
+            let work_areas = read_cardinals(b"_NET_WORKAREA\0");
+            xlib::XCloseDisplay(display);
+
+            // One (x, y, width, height) entry per desktop
+            let area = work_areas
+                .chunks_exact(4)
+                .nth(desktop)
+                .or_else(|| work_areas.chunks_exact(4).next())?;
+            Some((
+                area[0] as i32,
+                area[1] as i32,
+                area[2] as u32,
+                area[3] as u32,
+            ))
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // TODO: Implement for Windows and macOS
+        None
+    }
+}
+
+/// Set whole-window opacity (0.0 to 1.0) through `_NET_WM_WINDOW_OPACITY`
+///
+/// The compositing manager applies it on top of per-pixel alpha. Fully opaque removes the
+/// property, as compositors treat a missing property as opaque.
+pub fn set_window_opacity(window: &Arc<winit::window::Window>, opacity: f64) {
+    #[cfg(target_os = "linux")]
+    {
+        use x11::xlib;
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            let atom = xlib::XInternAtom(display, c"_NET_WM_WINDOW_OPACITY".as_ptr(), xlib::False);
+            if opacity >= 1.0 {
+                xlib::XDeleteProperty(display, x_window, atom);
+            } else {
+                // Format 32 properties are passed as an array of C longs
+                let value = (opacity.max(0.0) * u32::MAX as f64).round() as c_ulong;
+                xlib::XChangeProperty(
+                    display,
+                    x_window,
+                    atom,
+                    xlib::XA_CARDINAL,
+                    32,
+                    xlib::PropModeReplace,
+                    &value as *const c_ulong as *const u8,
+                    1,
+                );
+            }
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, opacity);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// Lock a window to a width to height ratio through its `WM_NORMAL_HINTS`, or unlock it
+///
+/// winit has no aspect ratio setting; the other size hints winit writes are kept.
+pub fn set_aspect_ratio(window: &Arc<winit::window::Window>, aspect_ratio: Option<(u32, u32)>) {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::raw::c_int;
+        use x11::xlib;
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            let mut hints: xlib::XSizeHints = std::mem::zeroed();
+            let mut supplied: c_long = 0;
+            xlib::XGetWMNormalHints(display, x_window, &mut hints, &mut supplied);
+
+            match aspect_ratio {
+                Some((width, height)) => {
+                    let ratio = xlib::AspectRatio {
+                        x: width.min(c_int::MAX as u32) as c_int,
+                        y: height.min(c_int::MAX as u32) as c_int,
+                    };
+                    hints.min_aspect = ratio;
+                    hints.max_aspect = ratio;
+                    hints.flags |= xlib::PAspect;
+                }
+                None => hints.flags &= !xlib::PAspect,
+            }
+            xlib::XSetWMNormalHints(display, x_window, &mut hints);
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, aspect_ratio);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// Mark a window as transient for a parent (`WM_TRANSIENT_FOR`), so the window manager
+/// keeps it above the parent and treats the two as a group
+pub fn set_transient_for(window: &Arc<winit::window::Window>, parent: &winit::window::Window) {
+    #[cfg(target_os = "linux")]
+    {
+        use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+        use x11::xlib;
+
+        let RawWindowHandle::Xlib(parent_handle) = parent.raw_window_handle() else {
+            return;
+        };
+        with_xlib_window(window, |display, x_window| unsafe {
+            xlib::XSetTransientForHint(display, x_window, parent_handle.window as xlib::Window);
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, parent);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// Add `_NET_WM_STATE` atoms (e.g. `_NET_WM_STATE_SKIP_TASKBAR`) to a window that has not
+/// been mapped yet; the window manager reads them when the window is shown
+pub fn init_net_wm_state(window: &Arc<winit::window::Window>, names: &[&CStr]) {
+    #[cfg(target_os = "linux")]
+    {
+        use x11::xlib;
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            let state = xlib::XInternAtom(display, c"_NET_WM_STATE".as_ptr(), xlib::False);
+            // Format 32 properties are passed as an array of C longs
+            let atoms: Vec<c_ulong> = names
+                .iter()
+                .map(|name| xlib::XInternAtom(display, name.as_ptr(), xlib::False))
+                .collect();
+            xlib::XChangeProperty(
+                display,
+                x_window,
+                state,
+                xlib::XA_ATOM,
+                32,
+                xlib::PropModeAppend,
+                atoms.as_ptr() as *const u8,
+                atoms.len() as i32,
+            );
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, names);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// Ask the window manager to add or remove a `_NET_WM_STATE` atom on a mapped window
+pub fn set_net_wm_state(window: &Arc<winit::window::Window>, name: &CStr, enabled: bool) {
+    #[cfg(target_os = "linux")]
+    {
+        use x11::xlib;
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            let mut event: xlib::XClientMessageEvent = std::mem::zeroed();
+            event.type_ = xlib::ClientMessage;
+            event.window = x_window;
+            event.message_type = xlib::XInternAtom(display, c"_NET_WM_STATE".as_ptr(), xlib::False);
+            event.format = 32;
+            // _NET_WM_STATE_ADD = 1, _NET_WM_STATE_REMOVE = 0; source 1 is a normal application
+            event.data.set_long(0, enabled as c_long);
+            event.data.set_long(
+                1,
+                xlib::XInternAtom(display, name.as_ptr(), xlib::False) as c_long,
+            );
+            event.data.set_long(3, 1);
+
+            let mut event = xlib::XEvent {
+                client_message: event,
+            };
+            xlib::XSendEvent(
+                display,
+                xlib::XDefaultRootWindow(display),
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+        });
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (window, name, enabled);
+        // TODO: Implement for Windows and macOS
+    }
+}
+
+/// Find a 32-bit TrueColor visual on the default screen whose XRender format has an alpha
+/// channel, so a window created with it keeps per-pixel transparency
+pub fn find_argb_visual() -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::raw::c_int;
+        use x11::{xlib, xrender};
+
+        unsafe {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            let mut template: xlib::XVisualInfo = std::mem::zeroed();
+            template.screen = xlib::XDefaultScreen(display);
+            template.depth = 32;
+            template.class = xlib::TrueColor;
+            let mut count: c_int = 0;
+            let infos = xlib::XGetVisualInfo(
+                display,
+                xlib::VisualScreenMask | xlib::VisualDepthMask | xlib::VisualClassMask,
+                &mut template,
+                &mut count,
+            );
+
+            let mut visual_id = None;
+            if !infos.is_null() {
+                for info in std::slice::from_raw_parts(infos, count as usize) {
+                    let format = xrender::XRenderFindVisualFormat(display, info.visual);
+                    if !format.is_null()
+                        && (*format).type_ == xrender::PictTypeDirect
+                        && (*format).direct.alphaMask != 0
+                    {
+                        visual_id = Some(info.visualid as u32);
+                        break;
+                    }
+                }
+                xlib::XFree(infos as *mut _);
+            }
+
+            xlib::XCloseDisplay(display);
+            visual_id
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // TODO: Implement for Windows and macOS
+        None
+    }
+}
+
+/// Whether a compositing manager is running, i.e. owns the `_NET_WM_CM_S<screen>` selection
+/// for the default screen. Without one, ARGB windows are drawn over black
+pub fn compositor_running() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        use std::ffi::CString;
+        use x11::xlib;
+
+        unsafe {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return false;
+            }
+
+            let name = CString::new(format!("_NET_WM_CM_S{}", xlib::XDefaultScreen(display)))
+                .expect("atom name has no NUL bytes");
+            let atom = xlib::XInternAtom(display, name.as_ptr(), xlib::False);
+            let owner = xlib::XGetSelectionOwner(display, atom);
+
+            xlib::XCloseDisplay(display);
+            owner != 0
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // TODO: Implement for Windows and macOS
+        false
+    }
+}
+
+/// Depth of the visual a window was created with, e.g. 32 for ARGB visuals
+pub fn window_depth(window: &Arc<winit::window::Window>) -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        use x11::xlib;
+
+        with_xlib_window(window, |display, x_window| unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if xlib::XGetWindowAttributes(display, x_window, &mut attributes) == 0 {
+                return None;
+            }
+            Some(attributes.depth as u32)
+        })
+        .flatten()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = window;
+        // TODO: Implement for Windows and macOS
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::window_manager::blend::premultiply;
+
+    /// A typical 32-bit ARGB visual on a little-endian server
+    const ARGB32: VisualFormat = VisualFormat {
+        depth: 32,
+        bits_per_pixel: 32,
+        scanline_pad: 32,
+        red_mask: 0xFF0000,
+        green_mask: 0x00FF00,
+        blue_mask: 0x0000FF,
+        big_endian: false,
+    };
+
+    /// Framebuffer ARGB pixels through the same path `render_window` and
+    /// `present_to_window` take, as one row
+    fn to_visual(pixels: &[u32], format: &VisualFormat) -> (Vec<u8>, usize) {
+        let rgba: Vec<u8> = pixels.iter().flat_map(|&argb| premultiply(argb)).collect();
+        pixmap_to_visual(&rgba, pixels.len() as u32, format).unwrap()
+    }
+
+    #[test]
+    fn argb_visual_gets_premultiplied_bgra() {
+        assert_eq!(
+            to_visual(&[0xFF102030, 0x80FF0000, 0x00FFFFFF], &ARGB32),
+            (
+                vec![0x30, 0x20, 0x10, 0xFF, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00],
+                12
+            )
+        );
+    }
+
+    #[test]
+    fn depth_24_visual_with_32_bits_per_pixel() {
+        let format = VisualFormat {
+            depth: 24,
+            ..ARGB32
+        };
+        // No alpha bits: the padding byte stays zero and colors are drawn over black
+        assert_eq!(
+            to_visual(&[0xFF102030, 0x80FF0000], &format),
+            (vec![0x30, 0x20, 0x10, 0x00, 0x00, 0x00, 0x80, 0x00], 8)
+        );
+    }
+
+    #[test]
+    fn packed_24_bits_per_pixel_pads_rows() {
+        let format = VisualFormat {
+            depth: 24,
+            bits_per_pixel: 24,
+            ..ARGB32
+        };
+        let (data, bytes_per_line) = to_visual(&[0xFF102030, 0x80FF0000, 0x00FFFFFF], &format);
+        assert_eq!(bytes_per_line, 12);
+        assert_eq!(
+            data,
+            [0x30, 0x20, 0x10, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn rgb565_visual() {
+        let format = VisualFormat {
+            depth: 16,
+            bits_per_pixel: 16,
+            scanline_pad: 32,
+            red_mask: 0xF800,
+            green_mask: 0x07E0,
+            blue_mask: 0x001F,
+            big_endian: false,
+        };
+        let (data, bytes_per_line) = to_visual(&[0xFFFF0000, 0xFF00FF00, 0xFF0000FF], &format);
+        assert_eq!(bytes_per_line, 8);
+        assert_eq!(data, [0x00, 0xF8, 0xE0, 0x07, 0x1F, 0x00, 0x00, 0x00]);
+
+        // Mid grey rounds to the nearest 5 and 6 bit values: 16, 32, 16
+        let (data, _) = to_visual(&[0xFF808080], &format);
+        assert_eq!(
+            u16::from_le_bytes([data[0], data[1]]),
+            16 << 11 | 32 << 5 | 16
+        );
+    }
+
+    #[test]
+    fn big_endian_server_and_bgr_masks() {
+        let format = VisualFormat {
+            big_endian: true,
+            ..ARGB32
+        };
+        assert_eq!(
+            to_visual(&[0xFF102030], &format).0,
+            [0xFF, 0x10, 0x20, 0x30]
+        );
+
+        let bgr = VisualFormat {
+            red_mask: 0x0000FF,
+            blue_mask: 0xFF0000,
+            ..ARGB32
+        };
+        assert_eq!(to_visual(&[0xFF102030], &bgr).0, [0x10, 0x20, 0x30, 0xFF]);
+    }
+
+    #[test]
+    fn rejects_partial_byte_pixels() {
+        let format = VisualFormat {
+            depth: 4,
+            bits_per_pixel: 4,
+            scanline_pad: 8,
+            red_mask: 0x8,
+            green_mask: 0x4,
+            blue_mask: 0x2,
+            big_endian: false,
+        };
+        assert!(pixmap_to_visual(&[0, 0, 0, 255], 1, &format).is_none());
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> ShapeRect {
+        ShapeRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn empty_mask_has_no_rects() {
+        assert!(alpha_mask_rects(&[0x00FF_FFFF; 6], 3, 2, 1).is_empty());
+        assert!(alpha_mask_rects(&[], 0, 0, 1).is_empty());
+    }
+
+    #[test]
+    fn full_mask_is_one_rect() {
+        assert_eq!(
+            alpha_mask_rects(&[0xFF00_0000; 12], 4, 3, 255),
+            vec![rect(0, 0, 4, 3)]
+        );
+    }
+
+    #[test]
+    fn disjoint_runs_stay_separate_and_matching_runs_merge() {
+        let on = 0xFF00_0000;
+        #[rustfmt::skip]
+        let pixels = [
+            on, 0, on, on,
+            on, 0, on, on,
+            0,  0, on, 0,
+        ];
+        assert_eq!(
+            alpha_mask_rects(&pixels, 4, 3, 128),
+            vec![rect(0, 0, 1, 2), rect(2, 0, 2, 2), rect(2, 2, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn threshold_is_inclusive() {
+        let pixels = [0x7F00_0000, 0x8000_0000, 0x8100_0000];
+        assert_eq!(
+            alpha_mask_rects(&pixels, 3, 1, 0x80),
+            vec![rect(1, 0, 2, 1)]
+        );
+        assert_eq!(alpha_mask_rects(&pixels, 3, 1, 0), vec![rect(0, 0, 3, 1)]);
+    }
+
+    #[test]
+    fn scaling_rounds_edges_outwards() {
+        let rects = [rect(1, 1, 1, 3)];
+        assert_eq!(scale_rects(&rects, 1.0), rects.to_vec());
+        assert_eq!(scale_rects(&rects, 2.0), vec![rect(2, 2, 2, 6)]);
+        // 1.5..3.0 and 1.5..6.0 widen to whole pixels
+        assert_eq!(scale_rects(&rects, 1.5), vec![rect(1, 1, 2, 5)]);
+    }
+}